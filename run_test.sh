@@ -5,29 +5,26 @@ TARGET_BIN="./target/debug/capnpc-angy"
 files="${TEST_CASE_ROOT}/*"
 cargo build
 
-if [ -n $1 ]; then
+# Runs every pair in the test case, e.g. `C03-before.capnp` and `C03-brand-before.capnp` with their `-after.capnp`.
+run_test_case() {
   TEST_CASE=$1
-  before_file=${TEST_CASE_ROOT}/${TEST_CASE}/${TEST_CASE}-before.capnp
-  after_file=${TEST_CASE_ROOT}/${TEST_CASE}/${TEST_CASE}-after.capnp
-  if [ ! -f ${before_file} ]; then
-    exit 0
-  fi
-  if [ ! -f ${after_file} ]; then
-    exit 0
-  fi
-  ${TARGET_BIN} ${before_file} ${after_file}
+  for before_file in ${TEST_CASE_ROOT}/${TEST_CASE}/${TEST_CASE}-before.capnp ${TEST_CASE_ROOT}/${TEST_CASE}/${TEST_CASE}-*-before.capnp; do
+    after_file=${before_file%-before.capnp}-after.capnp
+    if [ ! -f ${before_file} ]; then
+      continue
+    fi
+    if [ ! -f ${after_file} ]; then
+      continue
+    fi
+    ${TARGET_BIN} ${before_file} ${after_file}
+  done
+}
+
+if [ -n "$1" ]; then
+  run_test_case $1
   exit 0
 fi
 
 for filepath in $files; do
-  TEST_CASE=$(basename $filepath)
-  before_file=${TEST_CASE_ROOT}/${TEST_CASE}/${TEST_CASE}-before.capnp
-  after_file=${TEST_CASE_ROOT}/${TEST_CASE}/${TEST_CASE}-after.capnp
-  if [ ! -f ${before_file} ]; then
-    continue
-  fi
-  if [ ! -f ${after_file} ]; then
-    continue
-  fi
-  ${TARGET_BIN} ${before_file} ${after_file}
-done
\ No newline at end of file
+  run_test_case $(basename $filepath)
+done
@@ -0,0 +1,99 @@
+use capnpc::codegen::GeneratorContext;
+
+use crate::is_broken::is_broken;
+use crate::locator::locate_findings;
+use crate::locator::Locator;
+use crate::report::Finding;
+use crate::report::Report;
+use crate::report::Rule;
+use crate::report::Severity;
+
+/// A CodeGeneratorRequest of one side of the comparison.
+pub struct Request {
+    pub message: capnp::message::Reader<capnp::serialize::OwnedSegments>,
+    /// the inputs which the requested files are compiled from, in the same order.
+    /// They are ignored unless there is one for each requested file, e.g. for a request read from stdin.
+    pub files: Vec<RequestedFile>,
+}
+
+pub struct RequestedFile {
+    /// the name to pair the file by when its ID is changed, e.g. the path relative to the compared directory.
+    /// None is the filename in the request.
+    pub name: Option<String>,
+    pub locator: Option<Locator>,
+}
+
+/// A requested file of either side, with what it is paired and located by.
+struct Entry<'a, 'b> {
+    ctx: &'a GeneratorContext<'b>,
+    id: u64,
+    name: String,
+    locator: Option<&'a Locator>,
+}
+
+/// Compares the requested files of both sides, and returns one combined report.
+/// Files are paired by their file ID, and by their name if the ID is changed.
+pub fn compare(base: &[Request], changed: &[Request]) -> Result<Report, Box<dyn std::error::Error>> {
+    let base_ctxs = base.iter().map(|x| GeneratorContext::new(&x.message)).collect::<capnp::Result<Vec<_>>>()?;
+    let changed_ctxs = changed.iter().map(|x| GeneratorContext::new(&x.message)).collect::<capnp::Result<Vec<_>>>()?;
+    let base_files = get_entries(base, &base_ctxs)?;
+    let changed_files = get_entries(changed, &changed_ctxs)?;
+
+    let mut report = Report::new();
+    for base_file in base_files.iter() {
+        let file = base_file.ctx.node_map[&base_file.id].get_display_name()?;
+        if let Some(changed_file) = changed_files.iter().find(|x| x.id == base_file.id) {
+            let mut file_report = is_broken(base_file.ctx, changed_file.ctx, base_file.id)?;
+            locate_findings(&mut file_report, base_file.ctx, base_file.locator, changed_file.ctx, changed_file.locator);
+            report.extend(file_report);
+        } else if let Some(changed_file) = changed_files.iter().find(|x| x.name == base_file.name) {
+            // The IDs of the nested nodes are derived from the file ID, so they are all changed as well.
+            let mut finding = Finding::new(Rule::C01, Severity::Breaking, "", "the id of the file is changed.")
+                .with_values(format!("@0x{:016x}", base_file.id), format!("@0x{:016x}", changed_file.id));
+            finding.file = file.to_string();
+            finding.node_id = Some(base_file.id);
+            report.push(finding);
+        } else {
+            let mut finding = Finding::new(Rule::C22, Severity::Breaking, "", "the file is removed.");
+            finding.file = file.to_string();
+            finding.node_id = Some(base_file.id);
+            report.push(finding);
+        }
+    }
+
+    for changed_file in changed_files.iter() {
+        if !base_files.iter().any(|x| x.id == changed_file.id || x.name == changed_file.name) {
+            let file = changed_file.ctx.node_map[&changed_file.id].get_display_name()?;
+            let mut finding = Finding::new(Rule::N10, Severity::Info, "", "the file is added.");
+            finding.file = file.to_string();
+            finding.node_id = Some(changed_file.id);
+            report.push(finding);
+        }
+    }
+    Ok(report)
+}
+
+fn get_entries<'a, 'b>(
+    requests: &'a [Request],
+    ctxs: &'a [GeneratorContext<'b>],
+) -> Result<Vec<Entry<'a, 'b>>, Box<dyn std::error::Error>> {
+    let mut entries = vec![];
+    for (request, ctx) in requests.iter().zip(ctxs.iter()) {
+        let requested_files = ctx.request.get_requested_files()?;
+        let has_inputs = request.files.len() == requested_files.len() as usize;
+        for (index, requested_file) in requested_files.iter().enumerate() {
+            let input = if has_inputs { request.files.get(index) } else { None };
+            let name = match input.and_then(|x| x.name.as_ref()) {
+                Some(name) => name.clone(),
+                None => requested_file.get_filename()?.to_string(),
+            };
+            entries.push(Entry {
+                ctx,
+                id: requested_file.get_id(),
+                name,
+                locator: input.and_then(|x| x.locator.as_ref()),
+            });
+        }
+    }
+    Ok(entries)
+}
@@ -0,0 +1,122 @@
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::ExitStatus;
+use std::process::Stdio;
+
+/// The side of the comparison which an input belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Base,
+    Changed,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Base => write!(f, "base"),
+            Side::Changed => write!(f, "changed"),
+        }
+    }
+}
+
+/// An error while reading or compiling one side of the comparison.
+#[derive(Debug)]
+pub enum CompileError {
+    /// The input file cannot be opened.
+    Open { side: Side, path: String, source: std::io::Error },
+    /// The compiler cannot be started, e.g. it is not installed.
+    Spawn { side: Side, program: String, source: std::io::Error },
+    /// The compiler exits with an error, e.g. for a syntax error in the schema.
+    Failed { side: Side, path: String, status: ExitStatus, stderr: String },
+    /// The input is not a valid CodeGeneratorRequest.
+    Read { side: Side, path: String, source: capnp::Error },
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::Open { side, path, source } => write!(f, "cannot open the {} schema {}: {}", side, path, source),
+            CompileError::Spawn { side, program, source } if source.kind() == std::io::ErrorKind::NotFound => write!(
+                f,
+                "cannot find `{}` to compile the {} schema. Install Cap'n Proto or give the compiler with --capnp-bin.",
+                program, side
+            ),
+            CompileError::Spawn { side, program, source } => write!(f, "cannot run `{}` to compile the {} schema: {}", program, side, source),
+            CompileError::Failed { side, path, status, stderr } => {
+                write!(f, "the {} schema {} failed to compile ({})", side, path, status)?;
+                if !stderr.trim().is_empty() {
+                    write!(f, ":\n{}", stderr.trim_end())?;
+                }
+                Ok(())
+            }
+            CompileError::Read { side, path, source } => write!(f, "cannot read the {} CodeGeneratorRequest from {}: {}", side, path, source),
+        }
+    }
+}
+
+impl std::error::Error for CompileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CompileError::Open { source, .. } | CompileError::Spawn { source, .. } => Some(source),
+            CompileError::Read { source, .. } => Some(source),
+            CompileError::Failed { .. } => None,
+        }
+    }
+}
+
+/// Compiles the schema files with `capnp compile -o-`, and returns the CodeGeneratorRequest written to stdout.
+/// Relative paths are resolved against `current_dir`, or against the current directory if it is None.
+pub fn run_capnp(
+    capnp_bin: &str,
+    no_standard_import: bool,
+    import_paths: Vec<PathBuf>,
+    src_prefixes: Vec<PathBuf>,
+    target_files: Vec<PathBuf>,
+    current_dir: Option<&Path>,
+    side: Side,
+) -> Result<Vec<u8>, CompileError> {
+    let mut command = Command::new(capnp_bin);
+    command.env_remove("PWD");
+    if let Some(current_dir) = current_dir {
+        command.current_dir(current_dir);
+    }
+    command.arg("compile").arg("-o").arg("-");
+    if no_standard_import {
+        command.arg("--no-standard-import");
+    }
+
+    for import_path in import_paths {
+        command.arg(format!("--import-path={}", import_path.display()));
+    }
+
+    for src_prefix in src_prefixes {
+        command.arg(format!("--src-prefix={}", src_prefix.display()));
+    }
+
+    command.args(&target_files);
+
+    command.stdin(Stdio::null());
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::piped());
+
+    // Both pipes are read until the process exits, so that neither of them fills up and blocks the compiler.
+    let output = command.output().map_err(|source| CompileError::Spawn {
+        side,
+        program: capnp_bin.to_string(),
+        source,
+    })?;
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    if !output.status.success() {
+        return Err(CompileError::Failed {
+            side,
+            path: target_files.iter().map(|x| x.display().to_string()).collect::<Vec<_>>().join(", "),
+            status: output.status,
+            stderr,
+        });
+    }
+    // Warnings are still worth showing when the compile succeeds.
+    eprint!("{}", stderr);
+    Ok(output.stdout)
+}
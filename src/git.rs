@@ -0,0 +1,164 @@
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+
+/// The schema files of the local repository at a git revision, exported to a temporary directory.
+/// Only `*.capnp` files are exported, which is all `capnp compile` reads. The directory is removed on drop.
+pub struct GitTree {
+    rev: String,
+    repository_root: PathBuf,
+    current_dir: PathBuf,
+    root: PathBuf,
+}
+
+impl GitTree {
+    pub fn export(rev: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let repository_root = PathBuf::from(String::from_utf8(run_git(&["rev-parse", "--show-toplevel"])?)?.trim_end());
+        let current_dir = std::env::current_dir()?.canonicalize()?;
+        let commit = format!("{}^{{commit}}", rev);
+        run_git(&["rev-parse", "--verify", "--quiet", &commit]).map_err(|_| format!("unknown git revision: {}", rev))?;
+
+        // The tree is only created after the directory, so that drop never removes a directory which is not ours.
+        let tree = GitTree {
+            rev: rev.to_string(),
+            repository_root,
+            current_dir,
+            root: create_temp_dir()?,
+        };
+        std::fs::create_dir_all(tree.get_current_dir())?;
+        // Each entry is `<mode> <type> <object>\t<path>`.
+        let entries = String::from_utf8(run_git(&["ls-tree", "-r", "-z", "--full-tree", rev])?)?;
+        let files: Vec<(&str, &str)> = entries
+            .split('\0')
+            .filter_map(|x| x.split_once('\t'))
+            .filter(|(_, file)| file.ends_with(".capnp"))
+            .filter_map(|(info, file)| match info.split(' ').collect::<Vec<_>>()[..] {
+                [_, "blob", object] => Some((object, file)),
+                // e.g. a submodule, which is a commit.
+                _ => None,
+            })
+            .collect();
+        let contents = read_blobs(&files.iter().map(|(object, _)| *object).collect::<Vec<_>>())?;
+        for ((_, file), content) in files.iter().zip(contents) {
+            let path = tree.root.join(file);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, content)?;
+        }
+        Ok(tree)
+    }
+
+    /// Returns the directory in the exported tree which corresponds to the current directory.
+    pub fn get_current_dir(&self) -> PathBuf {
+        match self.current_dir.strip_prefix(&self.repository_root) {
+            Ok(relative) => self.root.join(relative),
+            Err(_) => self.root.clone(),
+        }
+    }
+
+    /// Returns the path to pass to capnp, which runs in `get_current_dir()`, or None if it is not in the repository.
+    /// Relative paths in the repository are kept, so that the display names equal those of the working copy.
+    fn get_repository_path(&self, path: &str) -> Option<PathBuf> {
+        let absolute = self.current_dir.join(path);
+        let absolute = absolute.canonicalize().unwrap_or(absolute);
+        match absolute.strip_prefix(&self.repository_root) {
+            Ok(_) if Path::new(path).is_relative() => Some(PathBuf::from(path)),
+            Ok(relative) => Some(self.root.join(relative)),
+            Err(_) => None,
+        }
+    }
+
+    /// Returns the path of an import path or a source prefix to pass to capnp.
+    /// A path which is not in the repository, e.g. /usr/include, is passed as is.
+    pub fn get_path(&self, path: &str) -> PathBuf {
+        self.get_repository_path(path).unwrap_or_else(|| {
+            let absolute = self.current_dir.join(path);
+            absolute.canonicalize().unwrap_or(absolute)
+        })
+    }
+
+    /// Returns the path of a schema file to compile at the revision.
+    /// A schema file which is not in the repository has no revision, so it is an error.
+    pub fn get_schema_path(&self, path: &str) -> Result<PathBuf, String> {
+        self.get_repository_path(path)
+            .ok_or_else(|| format!("{} is not in the git repository at {}", path, self.repository_root.display()))
+    }
+
+    /// Returns the name of the file in the revision, e.g. `origin/main:schema/foo.capnp`.
+    pub fn get_display_name(&self, path: &str) -> String {
+        format!("{}:{}", self.rev, path)
+    }
+}
+
+impl Drop for GitTree {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Creates a new directory in the temporary directory, which no other process can have created.
+fn create_temp_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.subsec_nanos();
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    for attempt in 0..100 {
+        let root = std::env::temp_dir().join(format!("capnpc-angy-{}-{}-{}", std::process::id(), nanos, attempt));
+        match builder.create(&root) {
+            Ok(()) => return Ok(root),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("cannot create a temporary directory {}: {}", root.display(), e).into()),
+        }
+    }
+    Err("cannot create a temporary directory: too many attempts".into())
+}
+
+/// Reads the contents of the blobs by one `git cat-file --batch`, in the same order.
+fn read_blobs(objects: &[&str]) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+    let mut child = Command::new("git")
+        .args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("cannot run git: {}", e))?;
+    let input: String = objects.iter().map(|x| format!("{}\n", x)).collect();
+    let mut stdin = child.stdin.take().ok_or("cannot write to git cat-file")?;
+    // The input is written on another thread, so that git is not blocked by a full stdout while it is written.
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    writer.join().map_err(|_| "cannot write to git cat-file")??;
+    if !output.status.success() {
+        return Err(format!("git cat-file --batch failed: {}", String::from_utf8_lossy(&output.stderr).trim_end()).into());
+    }
+
+    // Each blob is `<object> blob <size>\n<content>\n`, and a missing one is `<object> missing\n`.
+    let mut contents = vec![];
+    let mut rest = &output.stdout[..];
+    for _ in objects {
+        let end = rest.iter().position(|x| *x == b'\n').ok_or("unexpected end of git cat-file output")?;
+        let header = String::from_utf8_lossy(&rest[..end]);
+        let size: usize = match header.split(' ').collect::<Vec<_>>()[..] {
+            [_, "blob", size] => size.parse()?,
+            _ => return Err(format!("unexpected git cat-file output: {}", header).into()),
+        };
+        rest = &rest[end + 1..];
+        contents.push(rest.get(..size).ok_or("unexpected end of git cat-file output")?.to_vec());
+        rest = rest.get(size + 1..).ok_or("unexpected end of git cat-file output")?;
+    }
+    Ok(contents)
+}
+
+fn run_git(args: &[&str]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| format!("cannot run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim_end()).into());
+    }
+    Ok(output.stdout)
+}
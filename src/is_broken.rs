@@ -1,7 +1,7 @@
-use core::str;
-use std::cmp::Ordering;
-
+use capnp::struct_list;
+use capnp::schema_capnp::brand;
 use capnp::schema_capnp::field;
+use capnp::schema_capnp::method;
 use capnp::schema_capnp::type_;
 use capnp::schema_capnp::value;
 use capnp::schema_capnp::node;
@@ -10,121 +10,529 @@ use capnp::schema_capnp::node::const_;
 use capnp::schema_capnp::node::enum_;
 use capnp::schema_capnp::node::interface;
 use capnp::schema_capnp::node::struct_;
-use capnp::raw::*;
-use capnpc::codegen::getter_text;
-use capnpc::codegen::*;
-use capnp::message;
 use capnpc::codegen::GeneratorContext;
 
+use crate::report::ComparedNode;
+use crate::report::Finding;
+use crate::report::Report;
+use crate::report::Rule;
+use crate::report::Severity;
+use crate::util::get_default_value_as_bytes;
+use crate::util::get_file_name;
+use crate::util::get_node_name;
+use crate::util::get_slot_layout;
+use crate::util::get_type_name;
+use crate::util::get_value_text;
+use crate::util::is_same_bit_pattern;
+use crate::util::Section;
+use crate::util::SlotLayout;
+
 pub fn is_broken(
     base_ctx: &GeneratorContext,
     changed_ctx: &GeneratorContext,
     node_id: u64,
-) -> Result<bool, Box<dyn std::error::Error>> {
+) -> Result<Report, Box<dyn std::error::Error>> {
+    // The path of a file is empty, and the path of a node is relative to its file.
+    let path = match base_ctx.node_map[&node_id].which()? {
+        node::File(_) => String::new(),
+        _ => get_node_name(base_ctx, node_id)?,
+    };
+    let mut report = is_broken_node(base_ctx, changed_ctx, node_id, &path)?;
+    report.set_file(&get_file_name(base_ctx, node_id)?);
+    Ok(report)
+}
+
+/// Compares the node under the given path, e.g. `Person.union.employment` for a group.
+fn is_broken_node(
+    base_ctx: &GeneratorContext,
+    changed_ctx: &GeneratorContext,
+    node_id: u64,
+    path: &str,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    let mut report = Report::new();
     let base_node = base_ctx.node_map[&node_id];
-    let changed_node = changed_ctx.node_map.get(&node_id);
-    if changed_node.is_none() {
-        println!("{} is broken.", base_node.get_display_name()?);
-        return Ok(false);
-    }
-    let changed_node = changed_node.unwrap();
+    let Some(changed_node) = changed_ctx.node_map.get(&node_id) else {
+        let (rule, severity) = get_deleted_rule(base_node)?;
+        report.push(Finding::new(rule, severity, path, "the id is deleted."));
+        report.set_node_id(node_id);
+        return Ok(report);
+    };
+
+    report.extend(is_broken_parameters(base_node, *changed_node, path)?);
 
-    let mut ret = false;
     match base_node.which()? {
         node::File(_) => {
             // Files other than the specified are considered to have no differences.
+            for nested_node in base_node.get_nested_nodes()? {
+                let nested_node = base_ctx.node_map[&nested_node.get_id()];
+                if !matches!(nested_node.which()?, node::Annotation(_)) {
+                    report.compared_nodes.push(ComparedNode {
+                        file: base_node.get_display_name()?.to_string(),
+                        path: get_node_name(base_ctx, nested_node.get_id())?,
+                    });
+                }
+            }
         }
         node::Struct(base_struct) => {
             if let node::Struct(changed_struct) = changed_node.which()? {
-                ret = is_broken_struct(base_ctx, base_struct, changed_ctx, changed_struct)?;
+                report.extend(is_broken_struct(base_ctx, base_struct, changed_ctx, changed_struct, path)?);
             } else {
-                println!("{} is broken.", base_node.get_display_name()?);
-                return Ok(true);
+                let (rule, severity) = get_deleted_rule(base_node)?;
+                report.push(Finding::new(rule, severity, path, "the id is used by another kind of node."));
+                report.set_node_id(node_id);
+                return Ok(report);
             }
         }
         node::Interface(base_interface) => {
             if let node::Interface(changed_interface) = changed_node.which()? {
-                ret =
-                    is_broken_interface(base_ctx, base_interface, changed_ctx, changed_interface)?;
+                report.extend(is_broken_interface(base_ctx, base_interface, changed_ctx, changed_interface, path)?);
             } else {
-                println!("{} is broken.", base_node.get_display_name()?);
-                return Ok(true);
+                let (rule, severity) = get_deleted_rule(base_node)?;
+                report.push(Finding::new(rule, severity, path, "the id is used by another kind of node."));
+                report.set_node_id(node_id);
+                return Ok(report);
             }
         }
         node::Const(base_const) => {
             if let node::Const(changed_const) = changed_node.which()? {
-                ret = is_broken_const(base_ctx, base_const, changed_ctx, changed_const)?;
+                report.extend(is_broken_const(base_ctx, base_const, changed_ctx, changed_const, path)?);
             } else {
-                println!("{} is broken.", base_node.get_display_name()?);
-                return Ok(true);
+                let (rule, severity) = get_deleted_rule(base_node)?;
+                report.push(Finding::new(rule, severity, path, "the id is used by another kind of node."));
+                report.set_node_id(node_id);
+                return Ok(report);
             }
         }
         node::Enum(base_enum) => {
             if let node::Enum(changed_enum) = changed_node.which()? {
-                ret = is_broken_enum(base_ctx, base_enum, changed_ctx, changed_enum)?;
+                report.extend(is_broken_enum(base_ctx, base_enum, changed_ctx, changed_enum, path)?);
             } else {
-                println!("{} is broken.", base_node.get_display_name()?);
-                return Ok(true);
+                let (rule, severity) = get_deleted_rule(base_node)?;
+                report.push(Finding::new(rule, severity, path, "the id is used by another kind of node."));
+                report.set_node_id(node_id);
+                return Ok(report);
             }
         }
         node::Annotation(_) => {
             // annotation doesn't cause breaking change.
         }
     }
-    if ret == true {
-        println!("{} is broken", base_node.get_display_name()?);
-        return Ok(true);
-    }
+    report.set_node_id(node_id);
 
-    for nested_node in base_node.get_nested_nodes().unwrap() {
-        ret = is_broken(base_ctx, changed_ctx, nested_node.get_id())?;
+    for nested_node in base_node.get_nested_nodes()? {
+        report.extend(is_broken(base_ctx, changed_ctx, nested_node.get_id())?);
     }
 
+    Ok(report)
+}
+
+/// Returns the rule and the severity for a node whose id is not found in the changed schema.
+fn get_deleted_rule(base_node: node::Reader) -> Result<(Rule, Severity), Box<dyn std::error::Error>> {
+    let ret = match base_node.which()? {
+        node::File(_) => (Rule::C01, Severity::Breaking),
+        node::Interface(_) => (Rule::C09, Severity::Breaking),
+        node::Struct(_) | node::Enum(_) => (Rule::C02, Severity::Breaking),
+        // A const or an annotation never appears on the wire, so only the source is broken.
+        node::Const(_) | node::Annotation(_) => (Rule::C02, Severity::Warning),
+    };
     Ok(ret)
 }
 
+/// Compares the generic type parameters of two nodes.
+/// Brands refer to type parameters by index, so deleting or reordering a parameter breaks every brand which refers to the node.
+/// Adding a parameter at the end is not a breaking change. See N03.
+pub fn is_broken_parameters(
+    base_node: node::Reader,
+    changed_node: node::Reader,
+    path: &str,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    let mut report = Report::new();
+    if !base_node.get_is_generic() {
+        // neither this node nor its parent scopes have type parameters.
+        return Ok(report);
+    }
+
+    let base_names = base_node.get_parameters()?.iter().map(|x| x.get_name()).collect::<capnp::Result<Vec<_>>>()?;
+    let changed_names = changed_node.get_parameters()?.iter().map(|x| x.get_name()).collect::<capnp::Result<Vec<_>>>()?;
+    // Deletions are found first, so that the parameters after a deleted one are not blamed for it.
+    let is_shrunk = changed_names.len() < base_names.len();
+    for (index, base_name) in base_names.iter().enumerate() {
+        let parameter_path = format!("{}.{}", path, base_name);
+        match changed_names.iter().position(|x| x == base_name) {
+            Some(changed_index) if changed_index == index => {}
+            Some(changed_index) => {
+                report.push(
+                    Finding::new(Rule::C21, Severity::Breaking, &parameter_path, "the type parameter is moved to another index.")
+                        .with_values(index.to_string(), changed_index.to_string()),
+                );
+            }
+            // Only the name is changed, so brands which refer to this parameter by index still work.
+            None if !is_shrunk && !base_names.contains(&changed_names[index]) => {
+                report.push(
+                    Finding::new(Rule::M03, Severity::Warning, &parameter_path, "the name of the type parameter is changed, which breaks the source.")
+                        .with_values(*base_name, changed_names[index]),
+                );
+            }
+            None => {
+                report.push(Finding::new(Rule::C21, Severity::Breaking, &parameter_path, "the type parameter is deleted."));
+            }
+        }
+    }
+    Ok(report)
+}
+
 pub fn is_broken_struct(
     base_ctx: &GeneratorContext,
     base_struct: struct_::Reader,
     changed_ctx: &GeneratorContext,
     changed_struct: struct_::Reader,
-) -> Result<bool, Box<dyn std::error::Error>> {
-    // Please note that  .fields() method returns field iterator ordered by `@` annotation in the schema.
-    base_struct
-        .get_fields()?
-        .iter()
-        .zip(changed_struct.get_fields()?.iter())
-        .fold(Ok(false), |sum: Result<bool, Box<dyn std::error::Error>>, (x, y)| Ok(sum? || is_broken_field(base_ctx, x, changed_ctx, y)?))
+    path: &str,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    // Please note that the index of a field in .get_fields() is not always its ordinal, because groups
+    // and unions share the ordinal space with their members. So fields are paired by ordinal here.
+    let base_fields = base_struct.get_fields()?;
+    let changed_fields = changed_struct.get_fields()?;
+    let mut report = is_broken_struct_layout(base_struct, changed_struct, path)?;
+    for base_field in base_fields.iter() {
+        let field_path = get_field_path(base_struct, base_field, path)?;
+        let mut field_report = if let Some(changed_field) = find_field(changed_fields, base_field)? {
+            is_broken_field(base_ctx, base_field, changed_ctx, changed_field, &field_path)?
+        } else {
+            let mut field_report = Report::new();
+            field_report.push(Finding::new(Rule::C08, Severity::Breaking, &field_path, "the field is deleted."));
+            field_report
+        };
+        if let field::ordinal::Explicit(ordinal) = base_field.get_ordinal().which()? {
+            field_report.set_ordinal(ordinal);
+        }
+        report.extend(field_report);
+    }
+
+    for changed_field in changed_fields.iter() {
+        if find_field(base_fields, changed_field)?.is_none() {
+            let field_path = get_field_path(changed_struct, changed_field, path)?;
+            let mut finding = Finding::new(Rule::N02, Severity::Info, &field_path, "the field is added.");
+            if let field::ordinal::Explicit(ordinal) = changed_field.get_ordinal().which()? {
+                finding = finding.with_ordinal(ordinal);
+            }
+            report.push(finding);
+        }
+    }
+    Ok(report)
+}
+
+/// Returns the path of a field, e.g. `Person.union.phones`.
+fn get_field_path(struct_: struct_::Reader, field: field::Reader, path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut field_path = if field.get_discriminant_value() == field::NO_DISCRIMINANT {
+        path.to_string()
+    } else {
+        get_union_path(struct_, path)?
+    };
+    field_path.push('.');
+    field_path.push_str(field.get_name()?);
+    Ok(field_path)
+}
+
+/// Returns the path of the union in a struct (or a group).
+fn get_union_path(struct_: struct_::Reader, path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    // A named union is a group whose members are all in the union, and it is already named by the path.
+    if struct_.get_is_group() && u32::from(struct_.get_discriminant_count()) == struct_.get_fields()?.len() {
+        return Ok(path.to_string());
+    }
+    Ok(format!("{}.union", path))
+}
+
+pub fn is_broken_struct_layout(
+    base_struct: struct_::Reader,
+    changed_struct: struct_::Reader,
+    path: &str,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    let mut report = is_broken_discriminant(base_struct, changed_struct, path)?;
+    if base_struct.get_is_group() {
+        // A group shares the sections of the struct that contains it.
+        return Ok(report);
+    }
+
+    if changed_struct.get_data_word_count() < base_struct.get_data_word_count() {
+        report.push(
+            Finding::new(Rule::C19, Severity::Breaking, path, "the data section is shrunk.")
+                .with_values(
+                    format!("{} words", base_struct.get_data_word_count()),
+                    format!("{} words", changed_struct.get_data_word_count()),
+                ),
+        );
+    }
+    if changed_struct.get_pointer_count() < base_struct.get_pointer_count() {
+        report.push(
+            Finding::new(Rule::C19, Severity::Breaking, path, "the pointer section is shrunk.")
+                .with_values(
+                    format!("{} pointers", base_struct.get_pointer_count()),
+                    format!("{} pointers", changed_struct.get_pointer_count()),
+                ),
+        );
+    }
+    if base_struct.get_preferred_list_encoding()? != changed_struct.get_preferred_list_encoding()? {
+        // Lists of this struct are encoded differently, but readers of both versions can decode either encoding.
+        report.push(
+            Finding::new(Rule::N11, Severity::Info, path, "the preferred list encoding is changed.")
+                .with_values(
+                    format!("{:?}", base_struct.get_preferred_list_encoding()?),
+                    format!("{:?}", changed_struct.get_preferred_list_encoding()?),
+                ),
+        );
+    }
+    Ok(report)
+}
+
+/// Compares the unnamed union of two structs (or groups).
+/// Please note that the discriminant value of each union member is checked at is_broken_field method.
+pub fn is_broken_discriminant(
+    base_struct: struct_::Reader,
+    changed_struct: struct_::Reader,
+    path: &str,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    let mut report = Report::new();
+    let base_count = base_struct.get_discriminant_count();
+    let changed_count = changed_struct.get_discriminant_count();
+    if base_count == 0 && changed_count == 0 {
+        return Ok(report);
+    }
+    let union_path = if base_count == 0 {
+        get_union_path(changed_struct, path)?
+    } else {
+        get_union_path(base_struct, path)?
+    };
+    if base_count == 0 {
+        // Old readers ignore the new union, unless existing fields are moved into it. See C05.
+        report.push(Finding::new(Rule::M01, Severity::Warning, &union_path, "the union is added."));
+        return Ok(report);
+    }
+    if changed_count == 0 {
+        report.push(Finding::new(Rule::C06, Severity::Breaking, &union_path, "the union is deleted."));
+        return Ok(report);
+    }
+
+    if base_struct.get_discriminant_offset() != changed_struct.get_discriminant_offset() {
+        // The offset is in multiples of 16 bits.
+        report.push(
+            Finding::new(Rule::C05, Severity::Breaking, &union_path, "the discriminant is moved.")
+                .with_values(
+                    format!("data section bit {}", base_struct.get_discriminant_offset() * 16),
+                    format!("data section bit {}", changed_struct.get_discriminant_offset() * 16),
+                ),
+        );
+    }
+    if changed_count > base_count {
+        // Old readers regard the union as having an unknown member.
+        report.push(
+            Finding::new(Rule::M01, Severity::Warning, &union_path, "a member is added to the union, which old readers do not know.")
+                .with_values(format!("{} members", base_count), format!("{} members", changed_count)),
+        );
+    } else if changed_count < base_count {
+        report.push(
+            Finding::new(Rule::C06, Severity::Breaking, &union_path, "a member is deleted from the union.")
+                .with_values(format!("{} members", base_count), format!("{} members", changed_count)),
+        );
+    }
+    Ok(report)
+}
+
+/// Finds the field paired with `target` in `fields`.
+/// Fields with an explicit ordinal are paired by ordinal, and groups and unions, which have no ordinal, are paired by name.
+fn find_field<'a>(
+    fields: struct_list::Reader<'a, field::Owned>,
+    target: field::Reader,
+) -> Result<Option<field::Reader<'a>>, Box<dyn std::error::Error>> {
+    for field in fields.iter() {
+        let is_pair = match (target.get_ordinal().which()?, field.get_ordinal().which()?) {
+            (field::ordinal::Explicit(target_ordinal), field::ordinal::Explicit(ordinal)) => target_ordinal == ordinal,
+            (field::ordinal::Implicit(()), field::ordinal::Implicit(())) => target.get_name()? == field.get_name()?,
+            _ => false,
+        };
+        if is_pair {
+            return Ok(Some(field));
+        }
+    }
+    Ok(None)
 }
 
 pub fn is_broken_interface(
-    _: &GeneratorContext,
-    _: interface::Reader,
-    _: &GeneratorContext,
-    _: interface::Reader,
-) -> Result<bool, Box<dyn std::error::Error>> {
-    //
-    Ok(false)
+    base_ctx: &GeneratorContext,
+    base_interface: interface::Reader,
+    changed_ctx: &GeneratorContext,
+    changed_interface: interface::Reader,
+    path: &str,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    // Please note that .get_methods() returns methods ordered by `@` annotation in the schema,
+    // so the index in the list is the ordinal of the method.
+    let base_methods = base_interface.get_methods()?;
+    let changed_methods = changed_interface.get_methods()?;
+    let mut report = Report::new();
+    for (ordinal, base_method) in base_methods.iter().enumerate() {
+        let method_path = format!("{}.{}", path, base_method.get_name()?);
+        if ordinal as u32 >= changed_methods.len() {
+            report.push(Finding::new(Rule::C20, Severity::Breaking, &method_path, "the method is deleted.").with_ordinal(ordinal as u16));
+            continue;
+        }
+        let changed_method = changed_methods.get(ordinal as u32);
+        if base_method.get_name()? != changed_method.get_name()? {
+            let rule = if changed_methods.iter().any(|x| x.get_name().ok() == base_method.get_name().ok()) {
+                // another method is inserted before this method.
+                Rule::C16
+            } else {
+                Rule::C15
+            };
+            report.push(
+                Finding::new(rule, Severity::Breaking, &method_path, "the method at this ordinal is changed.")
+                    .with_values(base_method.get_name()?, changed_method.get_name()?)
+                    .with_ordinal(ordinal as u16),
+            );
+            continue;
+        }
+        let mut method_report = is_broken_method(base_ctx, base_method, changed_ctx, changed_method, &method_path)?;
+        method_report.set_ordinal(ordinal as u16);
+        report.extend(method_report);
+    }
+
+    for (ordinal, changed_method) in changed_methods.iter().enumerate().skip(base_methods.len() as usize) {
+        let method_path = format!("{}.{}", path, changed_method.get_name()?);
+        report.push(Finding::new(Rule::N05, Severity::Info, &method_path, "the method is added.").with_ordinal(ordinal as u16));
+    }
+    Ok(report)
+}
+
+pub fn is_broken_method(
+    base_ctx: &GeneratorContext,
+    base_method: method::Reader,
+    changed_ctx: &GeneratorContext,
+    changed_method: method::Reader,
+    path: &str,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    let mut report = Report::new();
+    let params_path = format!("{}.params", path);
+    if is_broken_brand(base_method.get_param_brand()?, changed_method.get_param_brand()?)? {
+        report.push(Finding::new(Rule::C10, Severity::Breaking, &params_path, "the type arguments of the params are changed."));
+    }
+    for mut finding in is_broken_method_struct(base_ctx, base_method.get_param_struct_type(), changed_ctx, changed_method.get_param_struct_type(), &params_path)?.findings {
+        finding.rule = finding.rule.for_params();
+        report.push(finding);
+    }
+
+    let results_path = format!("{}.results", path);
+    if is_broken_brand(base_method.get_result_brand()?, changed_method.get_result_brand()?)? {
+        report.push(Finding::new(Rule::C14, Severity::Breaking, &results_path, "the type arguments of the results are changed."));
+    }
+    for mut finding in is_broken_method_struct(base_ctx, base_method.get_result_struct_type(), changed_ctx, changed_method.get_result_struct_type(), &results_path)?.findings {
+        finding.rule = finding.rule.for_results();
+        report.push(finding);
+    }
+    Ok(report)
+}
+
+/// Compares the param or result struct of a method.
+/// Please note that findings are reported with the rules for struct, e.g. C03 for a changed type.
+pub fn is_broken_method_struct(
+    base_ctx: &GeneratorContext,
+    base_type_id: u64,
+    changed_ctx: &GeneratorContext,
+    changed_type_id: u64,
+    path: &str,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    let mut report = Report::new();
+    if base_type_id != changed_type_id {
+        report.push(
+            Finding::new(Rule::C03, Severity::Breaking, path, "the method refers to another struct.")
+                .with_values(get_node_name(base_ctx, base_type_id)?, get_node_name(changed_ctx, changed_type_id)?),
+        );
+        return Ok(report);
+    }
+    let (Some(base_node), Some(changed_node)) = (base_ctx.node_map.get(&base_type_id), changed_ctx.node_map.get(&changed_type_id)) else {
+        report.push(Finding::new(Rule::C03, Severity::Breaking, path, "the struct is not found."));
+        return Ok(report);
+    };
+    if base_node.get_scope_id() != 0 {
+        // Named structs are checked as nodes of their own, so only the auto-generated
+        // param and result structs are checked here.
+        return Ok(report);
+    }
+    match (base_node.which()?, changed_node.which()?) {
+        (node::Struct(base_struct), node::Struct(changed_struct)) => {
+            report.extend(is_broken_struct(base_ctx, base_struct, changed_ctx, changed_struct, path)?);
+        }
+        _ => {
+            report.push(Finding::new(Rule::C03, Severity::Breaking, path, "the id is used by another kind of node."));
+        }
+    }
+    report.set_node_id(base_type_id);
+    Ok(report)
 }
 
 pub fn is_broken_const(
-    _: &GeneratorContext,
-    _: const_::Reader,
-    _: &GeneratorContext,
-    _: const_::Reader,
-) -> Result<bool, Box<dyn std::error::Error>> {
-    //
-    Ok(false)
+    base_ctx: &GeneratorContext,
+    base_const: const_::Reader,
+    changed_ctx: &GeneratorContext,
+    changed_const: const_::Reader,
+    path: &str,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    // A const never appears on the wire, so a changed const only breaks the source.
+    let mut report = Report::new();
+    if is_broken_type(base_const.get_type()?, changed_const.get_type()?)? {
+        report.push(
+            Finding::new(Rule::M04, Severity::Warning, path, "the type of the const is changed, which breaks the source.")
+                .with_values(get_type_name(base_ctx, base_const.get_type()?)?, get_type_name(changed_ctx, changed_const.get_type()?)?),
+        );
+        return Ok(report);
+    }
+    if is_broken_value(base_const.get_value()?, changed_const.get_value()?)? {
+        report.push(
+            Finding::new(Rule::M05, Severity::Warning, path, "the value of the const is changed, which breaks the semantics.")
+                .with_values(
+                    get_value_text(base_ctx, base_const.get_value()?, base_const.get_type()?)?,
+                    get_value_text(changed_ctx, changed_const.get_value()?, changed_const.get_type()?)?,
+                ),
+        );
+    }
+    Ok(report)
 }
 
 pub fn is_broken_enum(
     _: &GeneratorContext,
-    _: enum_::Reader,
+    base_enum: enum_::Reader,
     _: &GeneratorContext,
-    _: enum_::Reader,
-) -> Result<bool, Box<dyn std::error::Error>> {
-    //
-    Ok(false)
+    changed_enum: enum_::Reader,
+    path: &str,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    // Please note that .get_enumerants() returns enumerants ordered by `@` annotation in the schema,
+    // so the index in the list is the ordinal of the enumerant.
+    let base_enumerants = base_enum.get_enumerants()?;
+    let changed_enumerants = changed_enum.get_enumerants()?;
+    let mut report = Report::new();
+    for (ordinal, base_enumerant) in base_enumerants.iter().enumerate() {
+        let enumerant_path = format!("{}.{}", path, base_enumerant.get_name()?);
+        if ordinal as u32 >= changed_enumerants.len() {
+            report.push(Finding::new(Rule::C17, Severity::Breaking, &enumerant_path, "the enumerant is deleted.").with_ordinal(ordinal as u16));
+            continue;
+        }
+        let changed_name = changed_enumerants.get(ordinal as u32).get_name()?;
+        if base_enumerant.get_name()? != changed_name {
+            // enumerant is renamed, or another enumerant is inserted at this ordinal.
+            report.push(
+                Finding::new(Rule::C18, Severity::Breaking, &enumerant_path, "the name of the enumerant is changed.")
+                    .with_values(base_enumerant.get_name()?, changed_name)
+                    .with_ordinal(ordinal as u16),
+            );
+        }
+    }
+
+    for (ordinal, changed_enumerant) in changed_enumerants.iter().enumerate().skip(base_enumerants.len() as usize) {
+        // Appending an enumerant is not a breaking change, but old readers receive a value they do not know.
+        let enumerant_path = format!("{}.{}", path, changed_enumerant.get_name()?);
+        report.push(
+            Finding::new(Rule::M02, Severity::Warning, &enumerant_path, "the enumerant is added, which old readers do not know.")
+                .with_ordinal(ordinal as u16),
+        );
+    }
+    Ok(report)
 }
 
 pub fn is_broken_field(
@@ -132,34 +540,56 @@ pub fn is_broken_field(
     base_field: field::Reader,
     changed_ctx: &GeneratorContext,
     changed_field: field::Reader,
-) -> Result<bool, Box<dyn std::error::Error>> {
-    let is_not_broken = 
-        base_field.get_name()? == changed_field.get_name()? &&
-        base_field.get_discriminant_value() == changed_field.get_discriminant_value();
-    if !is_not_broken{
-        println!("{} is broken", base_field.get_name()?);
-        return Ok(true)
-    }
-    return match base_field.which()? {
+    path: &str,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    let mut report = Report::new();
+    if base_field.get_name()? != changed_field.get_name()? {
+        report.push(
+            Finding::new(Rule::C04, Severity::Breaking, path, "the name of the field is changed.")
+                .with_values(base_field.get_name()?, changed_field.get_name()?),
+        );
+        return Ok(report);
+    }
+    if base_field.get_discriminant_value() != changed_field.get_discriminant_value() {
+        let rule = if base_field.get_discriminant_value() == field::NO_DISCRIMINANT {
+            Rule::C05
+        } else if changed_field.get_discriminant_value() == field::NO_DISCRIMINANT {
+            Rule::C06
+        } else {
+            // the field is moved in the union.
+            Rule::C05
+        };
+        report.push(
+            Finding::new(rule, Severity::Breaking, path, "the discriminant value of the field is changed.")
+                .with_values(get_discriminant_text(base_field), get_discriminant_text(changed_field)),
+        );
+        return Ok(report);
+    }
+    match base_field.which()? {
         field::Which::Group(base_group) => {
             if let field::Which::Group(changed_group) = changed_field.which()?{
-                is_broken_group(base_ctx, base_group, changed_ctx, changed_group)
+                report.extend(is_broken_group(base_ctx, base_group, changed_ctx, changed_group, path)?);
             } else {
-                println!("{} is broken", base_field.get_name()?);
-                Ok(true)
+                report.push(Finding::new(Rule::C03, Severity::Breaking, path, "the group becomes a field."));
             }
         }
         field::Which::Slot(base_slot) => {
             if let field::Which::Slot(changed_slot) = changed_field.which()?{
-                let (a, b, c) = getter_text(&base_ctx, &base_field, true, false)?;
-                println!("{}", a);
-                is_broken_slot(base_field, base_slot, &changed_field, changed_slot)
+                report.extend(is_broken_slot(base_ctx, base_slot, changed_ctx, changed_slot, path)?);
             } else {
-                println!("{} is broken", base_field.get_name()?);
-                Ok(true)
+                report.push(Finding::new(Rule::C03, Severity::Breaking, path, "the field becomes a group."));
             }
         }
-    };
+    }
+    Ok(report)
+}
+
+fn get_discriminant_text(field: field::Reader) -> String {
+    if field.get_discriminant_value() == field::NO_DISCRIMINANT {
+        "non-union".to_string()
+    } else {
+        format!("union member {}", field.get_discriminant_value())
+    }
 }
 
 pub fn is_broken_group(
@@ -167,39 +597,79 @@ pub fn is_broken_group(
     base_group: field::group::Reader,
     changed_ctx: &GeneratorContext,
     _: field::group::Reader,
-) -> Result<bool, Box<dyn std::error::Error>> {
-    is_broken(base_ctx, changed_ctx, base_group.get_type_id())
+    path: &str,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    is_broken_node(base_ctx, changed_ctx, base_group.get_type_id(), path)
 }
 
 pub fn is_broken_slot(
-    base_field: field::Reader,
+    base_ctx: &GeneratorContext,
     base_slot: field::slot::Reader,
-    _: &field::Reader,
+    changed_ctx: &GeneratorContext,
     changed_slot: field::slot::Reader,
-) -> Result<bool, Box<dyn std::error::Error>> {
+    path: &str,
+) -> Result<Report, Box<dyn std::error::Error>> {
     // Please note that `@` annotation order is checked at is_broken_struct method.
+    let mut report = Report::new();
     if is_broken_type(base_slot.get_type()?, changed_slot.get_type()?)? {
-        // type for this slot is changed.
-        println!("{} is broken.", base_field.get_name()?);
-        return Ok(true);
+        report.push(
+            Finding::new(Rule::C03, Severity::Breaking, path, "the type of the field is changed.")
+                .with_values(get_type_name(base_ctx, base_slot.get_type()?)?, get_type_name(changed_ctx, changed_slot.get_type()?)?),
+        );
+        return Ok(report);
     }
 
-    // TODO: Default type breaking change detection
-    // Even if a new default value is set, it is not a breaking change if it is 0x00000000 in binary representation. 
-    // An implementation that takes this into consideration is required.
-    if base_slot.has_default_value() != changed_slot.has_default_value() {
-        // default value is set or unset.
-        println!("{} is broken.", base_field.get_name()?);
-        return Ok(true);
+    let base_layout = get_slot_layout(base_slot)?;
+    let changed_layout = get_slot_layout(changed_slot)?;
+    if base_layout != changed_layout {
+        report.push(
+            Finding::new(Rule::C03, Severity::Breaking, path, "the field is moved to another position in the struct.")
+                .with_values(
+                    base_layout.map_or("no space".to_string(), |x| x.to_string()),
+                    changed_layout.map_or("no space".to_string(), |x| x.to_string()),
+                ),
+        );
+        return Ok(report);
     }
 
-    if base_slot.has_default_value() && changed_slot.has_default_value() && is_broken_value(base_slot.get_default_value()?, changed_slot.get_default_value()?)? {
-        // default value for this slot is changed.
-        println!("{} is broken.", base_field.get_name()?);
-        return Ok(true);
+    let is_broken_default = if let Some(SlotLayout { section: Section::Data, .. }) = base_layout {
+        // A primitive field is XORed with its default value on the wire, so an explicit default that equals
+        // the zero value (e.g. `= 0` or `= false`) is the same as no default in binary representation.
+        let base_default = if base_slot.has_default_value() {
+            get_default_value_as_bytes(base_slot.get_default_value()?)?
+        } else {
+            vec![]
+        };
+        let changed_default = if changed_slot.has_default_value() {
+            get_default_value_as_bytes(changed_slot.get_default_value()?)?
+        } else {
+            vec![]
+        };
+        !is_same_bit_pattern(&base_default, &changed_default)
+    } else if base_slot.has_default_value() != changed_slot.has_default_value() {
+        // default value is set or unset.
+        true
+    } else {
+        base_slot.has_default_value() && is_broken_value(base_slot.get_default_value()?, changed_slot.get_default_value()?)?
+    };
+    if is_broken_default {
+        report.push(
+            Finding::new(Rule::C07, Severity::Breaking, path, "the default value of the field is changed.")
+                .with_values(
+                    get_default_value_text(base_ctx, base_slot)?,
+                    get_default_value_text(changed_ctx, changed_slot)?,
+                ),
+        );
     }
+    Ok(report)
+}
 
-    Ok(false)
+fn get_default_value_text(ctx: &GeneratorContext, slot: field::slot::Reader) -> Result<String, Box<dyn std::error::Error>> {
+    if slot.has_default_value() {
+        get_value_text(ctx, slot.get_default_value()?, slot.get_type()?)
+    } else {
+        Ok("none".to_string())
+    }
 }
 
 pub fn is_broken_value(
@@ -300,15 +770,14 @@ pub fn is_broken_value(
         },
         value::Which::Data(base_a1) => {
             if let value::Which::Data(changed_a1) = changed_value.which()? {
-                ret =  str::from_utf8(base_a1?) != str::from_utf8(changed_a1?);
+                ret = base_a1? != changed_a1?;
             } else {
                 ret = true;
             }
         },
-        value::Which::List(base_list) => {
-            if let value::Which::List(changed_list) = changed_value.which()? {
-                // TODO: Implement Eq for List value
-                ret = base_list.get_as::<&[u8]>()?.cmp(changed_list.get_as::<&[u8]>()?) == Ordering::Equal;
+        value::Which::List(_) => {
+            if let value::Which::List(_) = changed_value.which()? {
+                ret = is_broken_pointer_value(base_value, changed_value)?;
             } else {
                 ret = true;
             }
@@ -322,7 +791,7 @@ pub fn is_broken_value(
         },
         value::Which::Struct(_) => {
             if let value::Which::Struct(_) = changed_value.which()? {
-                // TODO: Implement Eq for Struct value
+                ret = is_broken_pointer_value(base_value, changed_value)?;
             } else if let value::Which::AnyPointer(_) = changed_value.which()? {
                 // In this case, field type becomes AnyPointer(or Generics). See N03.
             } else {
@@ -331,14 +800,14 @@ pub fn is_broken_value(
         },
         value::Which::Interface(()) => {
             if let value::Which::Interface(_) = changed_value.which()? {
-                // TODO: Implement Eq for Interface value
+                // The default value of an interface is always null.
             } else {
                 ret = true;
             }
         },
         value::Which::AnyPointer(_) => {
             if let value::Which::AnyPointer(_) = changed_value.which()? {
-                // TODO: Implement Eq for AnyPointer value
+                ret = is_broken_pointer_value(base_value, changed_value)?;
             } else {
                 ret = true;
             }
@@ -347,6 +816,15 @@ pub fn is_broken_value(
     Ok(ret)
 }
 
+/// Compares two pointer values by their canonical form, so that values with the same content are equal
+/// regardless of how they are laid out in the schema message.
+fn is_broken_pointer_value(
+    base_value: value::Reader,
+    changed_value: value::Reader,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(get_default_value_as_bytes(base_value)? != get_default_value_as_bytes(changed_value)?)
+}
+
 pub fn is_broken_type(
     base_type: type_::Reader,
     changed_type: type_::Reader,
@@ -453,7 +931,8 @@ pub fn is_broken_type(
         },
         type_::Which::Struct(base_a2) => {
             if let type_::Which::Struct(changed_a2) = changed_type.which()? {
-                ret = base_a2.get_type_id() != changed_a2.get_type_id();
+                ret = base_a2.get_type_id() != changed_a2.get_type_id() ||
+                    is_broken_brand(base_a2.get_brand()?, changed_a2.get_brand()?)?;
             } else if let type_::Which::AnyPointer(changed_a2) = changed_type.which()?{
                 match changed_a2.which()? {
                     type_::any_pointer::Which::Unconstrained(_) => {
@@ -472,14 +951,15 @@ pub fn is_broken_type(
         },
         type_::Which::Interface(base_a3) => {
             if let type_::Which::Interface(changed_a3) = changed_type.which()? {
-                ret = base_a3.get_type_id() != changed_a3.get_type_id();
+                ret = base_a3.get_type_id() != changed_a3.get_type_id() ||
+                    is_broken_brand(base_a3.get_brand()?, changed_a3.get_brand()?)?;
             } else {
                 ret = true;
             }
         },
-        type_::Which::AnyPointer(_) => {
-            if let type_::Which::AnyPointer(_) = changed_type.which()? {
-                // TODO: check if two AnyPointer types are same. 
+        type_::Which::AnyPointer(base_a4) => {
+            if let type_::Which::AnyPointer(changed_a4) = changed_type.which()? {
+                ret = is_broken_any_pointer(base_a4, changed_a4)?;
             } else {
                 ret = true;
             }
@@ -488,6 +968,77 @@ pub fn is_broken_type(
     Ok(ret)
 }
 
+pub fn is_broken_any_pointer(
+    base_any_pointer: type_::any_pointer::Reader,
+    changed_any_pointer: type_::any_pointer::Reader,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let ret = match (base_any_pointer.which()?, changed_any_pointer.which()?) {
+        (type_::any_pointer::Unconstrained(base_a0), type_::any_pointer::Unconstrained(changed_a0)) => {
+            !matches!(
+                (base_a0.which()?, changed_a0.which()?),
+                (type_::any_pointer::unconstrained::AnyKind(()), type_::any_pointer::unconstrained::AnyKind(()))
+                    | (type_::any_pointer::unconstrained::Struct(()), type_::any_pointer::unconstrained::Struct(()))
+                    | (type_::any_pointer::unconstrained::List(()), type_::any_pointer::unconstrained::List(()))
+                    | (type_::any_pointer::unconstrained::Capability(()), type_::any_pointer::unconstrained::Capability(()))
+            )
+        }
+        (type_::any_pointer::Parameter(base_a1), type_::any_pointer::Parameter(changed_a1)) => {
+            // the field refers to another type parameter.
+            base_a1.get_scope_id() != changed_a1.get_scope_id() ||
+                base_a1.get_parameter_index() != changed_a1.get_parameter_index()
+        }
+        (type_::any_pointer::ImplicitMethodParameter(base_a2), type_::any_pointer::ImplicitMethodParameter(changed_a2)) => {
+            base_a2.get_parameter_index() != changed_a2.get_parameter_index()
+        }
+        _ => true,
+    };
+    Ok(ret)
+}
+
+/// Compares the type arguments bound to generic types, scope by scope.
+/// A scope which is bound only in the changed brand is not a breaking change, because the type has become generic. See N03.
+pub fn is_broken_brand(
+    base_brand: brand::Reader,
+    changed_brand: brand::Reader,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let changed_scopes = changed_brand.get_scopes()?;
+    for base_scope in base_brand.get_scopes()?.iter() {
+        let Some(changed_scope) = changed_scopes.iter().find(|x| x.get_scope_id() == base_scope.get_scope_id()) else {
+            // type arguments for this scope are unbound.
+            return Ok(true);
+        };
+        match (base_scope.which()?, changed_scope.which()?) {
+            (brand::scope::Bind(base_bindings), brand::scope::Bind(changed_bindings)) => {
+                let base_bindings = base_bindings?;
+                let changed_bindings = changed_bindings?;
+                if base_bindings.len() != changed_bindings.len() {
+                    return Ok(true);
+                }
+                for (base_binding, changed_binding) in base_bindings.iter().zip(changed_bindings.iter()) {
+                    match (base_binding.which()?, changed_binding.which()?) {
+                        (brand::binding::Unbound(()), _) => {
+                            // binding an unbound parameter is the same as N03.
+                        }
+                        (brand::binding::Type(base_type), brand::binding::Type(changed_type)) => {
+                            if is_broken_type(base_type?, changed_type?)? {
+                                return Ok(true);
+                            }
+                        }
+                        (brand::binding::Type(_), brand::binding::Unbound(())) => {
+                            return Ok(true);
+                        }
+                    }
+                }
+            }
+            (brand::scope::Inherit(()), brand::scope::Inherit(())) => {}
+            _ => {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
 #[allow(dead_code)]
 pub fn is_broken_annotation(
     _: &GeneratorContext,
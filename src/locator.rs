@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use capnpc::codegen::GeneratorContext;
+
+use crate::report::Location;
+use crate::report::Report;
+use crate::util::get_node_name;
+
+/// Maps the names declared in a schema file to their positions, e.g. `Person.phones` to line 31, column 3.
+/// The CodeGeneratorRequest has no source positions, so the schema file is scanned lightly instead of parsed.
+pub struct Locator {
+    file: String,
+    positions: HashMap<String, (usize, usize)>,
+}
+
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl Locator {
+    pub fn from_file(path: &Path) -> std::io::Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Ok(Locator::new(&path.display().to_string(), &source))
+    }
+
+    pub fn new(file: &str, source: &str) -> Self {
+        let mut locator = Locator {
+            file: file.to_string(),
+            positions: HashMap::new(),
+        };
+        locator.scan(&tokenize(source));
+        locator
+    }
+
+    /// Returns the position of the name, or of its nearest declared parent, e.g. `Person` for `Person.union`.
+    pub fn find(&self, name: &str) -> Option<Location> {
+        let mut name = name;
+        loop {
+            if let Some((line, column)) = self.positions.get(name) {
+                return Some(Location {
+                    file: self.file.clone(),
+                    line: *line,
+                    column: *column,
+                });
+            }
+            name = name.rsplit_once('.')?.0;
+        }
+    }
+
+    fn scan(&mut self, tokens: &[Token]) {
+        // The names of the enclosing scopes. None is a block which is not recognized as a declaration.
+        let mut scopes: Vec<Option<&str>> = vec![];
+        let mut pending: Option<&str> = None;
+        let mut index = 0;
+        while index < tokens.len() {
+            let text = tokens[index].text;
+            let next = tokens.get(index + 1).map(|x| x.text);
+            match text {
+                "struct" | "interface" | "enum" if next.is_some_and(is_identifier) => {
+                    self.declare(&scopes, &tokens[index + 1]);
+                    pending = next;
+                    index += 2;
+                    continue;
+                }
+                "const" | "annotation" if next.is_some_and(is_identifier) => {
+                    self.declare(&scopes, &tokens[index + 1]);
+                    index += 2;
+                    continue;
+                }
+                "union" if next == Some("{") => {
+                    // an unnamed union. Its members are named like `Person.union.phones`.
+                    self.declare_as(&scopes, "union", &tokens[index]);
+                    pending = Some("union");
+                }
+                "{" => {
+                    scopes.push(pending.take());
+                }
+                "}" => {
+                    scopes.pop();
+                }
+                _ if is_identifier(text) && next == Some("@") => {
+                    // a field, an enumerant or a method.
+                    self.declare(&scopes, &tokens[index]);
+                    if tokens.get(index + 3).map(|x| x.text) == Some("(") {
+                        index = self.scan_method(&scopes, text, tokens, index + 3);
+                        continue;
+                    }
+                }
+                _ if is_identifier(text) && next == Some(":") => {
+                    // a named union or a group.
+                    if matches!(tokens.get(index + 2).map(|x| x.text), Some("union") | Some("group")) {
+                        self.declare(&scopes, &tokens[index]);
+                        pending = Some(text);
+                        index += 3;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            index += 1;
+        }
+    }
+
+    /// Declares the params and results of a method, and returns the index of the token after them.
+    fn scan_method(&mut self, scopes: &[Option<&str>], method: &str, tokens: &[Token], start: usize) -> usize {
+        let mut index = self.scan_params(scopes, &format!("{}.params", method), tokens, start);
+        if tokens.get(index).map(|x| x.text) == Some("-")
+            && tokens.get(index + 1).map(|x| x.text) == Some(">")
+            && tokens.get(index + 2).map(|x| x.text) == Some("(")
+        {
+            index = self.scan_params(scopes, &format!("{}.results", method), tokens, index + 2);
+        }
+        index
+    }
+
+    /// Declares the names in a parenthesized param list, and returns the index of the token after `)`.
+    fn scan_params(&mut self, scopes: &[Option<&str>], prefix: &str, tokens: &[Token], start: usize) -> usize {
+        let mut depth = 0;
+        let mut index = start;
+        while index < tokens.len() {
+            match tokens[index].text {
+                "(" | "[" => depth += 1,
+                ")" | "]" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return index + 1;
+                    }
+                }
+                text if depth == 1 && is_identifier(text) && tokens.get(index + 1).map(|x| x.text) == Some(":") => {
+                    self.declare_as(scopes, &format!("{}.{}", prefix, text), &tokens[index]);
+                }
+                _ => {}
+            }
+            index += 1;
+        }
+        index
+    }
+
+    fn declare(&mut self, scopes: &[Option<&str>], token: &Token) {
+        self.declare_as(scopes, token.text, token);
+    }
+
+    fn declare_as(&mut self, scopes: &[Option<&str>], name: &str, token: &Token) {
+        let mut names: Vec<&str> = scopes.iter().flatten().copied().collect();
+        names.push(name);
+        self.positions.entry(names.join(".")).or_insert((token.line, token.column));
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+}
+
+/// Splits the source into identifiers, numbers, strings and single punctuation characters. Comments are skipped.
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    for (line_index, line) in source.lines().enumerate() {
+        let mut chars = line.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            let column = line[..start].chars().count() + 1;
+            let end = if c == '#' {
+                break;
+            } else if c.is_whitespace() {
+                continue;
+            } else if c == '"' {
+                let mut end = line.len();
+                let mut escaped = false;
+                for (index, c) in chars.by_ref() {
+                    if c == '"' && !escaped {
+                        end = index + 1;
+                        break;
+                    }
+                    escaped = c == '\\' && !escaped;
+                }
+                end
+            } else if c.is_ascii_alphanumeric() || c == '_' {
+                let mut end = start + c.len_utf8();
+                while let Some((index, c)) = chars.peek().copied() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = index + c.len_utf8();
+                    chars.next();
+                }
+                end
+            } else {
+                start + c.len_utf8()
+            };
+            tokens.push(Token {
+                text: &line[start..end],
+                line: line_index + 1,
+                column,
+            });
+        }
+    }
+    tokens
+}
+
+/// Sets the positions in the base and changed schema files to the findings.
+pub fn locate_findings(
+    report: &mut Report,
+    base_ctx: &GeneratorContext,
+    base_locator: Option<&Locator>,
+    changed_ctx: &GeneratorContext,
+    changed_locator: Option<&Locator>,
+) {
+    for finding in report.findings.iter_mut() {
+        let name = finding.path.as_str();
+        if let Some(locator) = base_locator {
+            finding.base_location = locator.find(name);
+        }
+
+        let Some(locator) = changed_locator else {
+            continue;
+        };
+        // The node may be renamed with its id pinned (see N04), so the name in the changed schema is looked up by id.
+        let mut changed_name = name.to_string();
+        if let Some(node_id) = finding.node_id.filter(|x| changed_ctx.node_map.contains_key(x)) {
+            if let (Ok(base_node_name), Ok(changed_node_name)) = (get_node_name(base_ctx, node_id), get_node_name(changed_ctx, node_id)) {
+                if let Some(member) = name.strip_prefix(&base_node_name) {
+                    changed_name = format!("{}{}", changed_node_name, member);
+                }
+            }
+        }
+        // A renamed member is declared with its new name.
+        if !locator.positions.contains_key(&changed_name) {
+            if let (Some(changed), Some((parent, _))) = (&finding.changed, changed_name.rsplit_once('.')) {
+                let renamed = format!("{}.{}", parent, changed);
+                if locator.positions.contains_key(&renamed) {
+                    changed_name = renamed;
+                }
+            }
+        }
+        finding.changed_location = locator.find(&changed_name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use capnp::schema_capnp::code_generator_request;
+
+    use super::*;
+    use crate::report::Finding;
+    use crate::report::Rule;
+    use crate::report::Severity;
+
+    fn get_locator(name: &str) -> Locator {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-cases").join(&name[..3]).join(name);
+        Locator::from_file(&path).unwrap()
+    }
+
+    fn get_position(locator: &Locator, name: &str) -> Option<(usize, usize)> {
+        locator.find(name).map(|x| (x.line, x.column))
+    }
+
+    /// Returns a CodeGeneratorRequest which only has struct nodes with the given ids and display names.
+    fn get_request(nodes: &[(u64, &str)]) -> capnp::message::Reader<capnp::serialize::OwnedSegments> {
+        let mut message = capnp::message::Builder::new_default();
+        let request = message.init_root::<code_generator_request::Builder>();
+        let mut list = request.init_nodes(nodes.len() as u32);
+        for (index, (id, display_name)) in nodes.iter().enumerate() {
+            let mut node = list.reborrow().get(index as u32);
+            node.set_id(*id);
+            node.set_display_name(display_name);
+            node.init_struct();
+        }
+        let mut bytes = vec![];
+        capnp::serialize::write_message(&mut bytes, &message).unwrap();
+        capnp::serialize::read_message(bytes.as_slice(), capnp::message::ReaderOptions::new()).unwrap()
+    }
+
+    /// Locates a finding on the node, which is renamed from `base_name` to `changed_name` with its id pinned.
+    fn locate_renamed(test_case: &str, node_id: u64, base_name: &str, changed_name: &str, path: &str) -> Finding {
+        let base_message = get_request(&[(node_id, &format!("{}.capnp:{}", test_case, base_name))]);
+        let changed_message = get_request(&[(node_id, &format!("{}.capnp:{}", test_case, changed_name))]);
+        let base_ctx = GeneratorContext::new(&base_message).unwrap();
+        let changed_ctx = GeneratorContext::new(&changed_message).unwrap();
+        let base_locator = get_locator(&format!("{}-before.capnp", test_case));
+        let changed_locator = get_locator(&format!("{}-after.capnp", test_case));
+
+        let mut report = Report::new();
+        report.push(Finding::new(Rule::C03, Severity::Breaking, path, "the type of the field is changed."));
+        report.set_node_id(node_id);
+        locate_findings(&mut report, &base_ctx, Some(&base_locator), &changed_ctx, Some(&changed_locator));
+        report.findings.remove(0)
+    }
+
+    #[test]
+    fn test_find_field() {
+        let locator = get_locator("C03-before.capnp");
+        assert_eq!(get_position(&locator, "Person"), Some((27, 8)));
+        assert_eq!(get_position(&locator, "Person.email"), Some((30, 3)));
+        // an undeclared member is located at its nearest declared parent.
+        assert_eq!(get_position(&locator, "Person.unknown"), Some((27, 8)));
+        assert_eq!(get_position(&locator, "Unknown"), None);
+    }
+
+    #[test]
+    fn test_find_unnamed_union_member() {
+        let locator = get_locator("C03-before.capnp");
+        assert_eq!(get_position(&locator, "Person.union"), Some((33, 3)));
+        assert_eq!(get_position(&locator, "Person.union.a"), Some((34, 5)));
+    }
+
+    #[test]
+    fn test_find_method_params_and_results() {
+        let locator = get_locator("N03-before.capnp");
+        assert_eq!(get_position(&locator, "Sample.initialize"), Some((40, 5)));
+        assert_eq!(get_position(&locator, "Sample.initialize.params.test"), Some((40, 33)));
+        assert_eq!(get_position(&locator, "Sample.initialize.results.result"), Some((40, 71)));
+        assert_eq!(get_position(&locator, "Sample.Subscriber.pushMessage.results.result"), Some((43, 31)));
+    }
+
+    #[test]
+    fn test_locate_renamed_struct() {
+        let finding = locate_renamed("N04", 0x97a2bddf19f5a74a, "Person", "Human", "Person.name");
+        assert_eq!(finding.base_location.map(|x| (x.line, x.column)), Some((28, 3)));
+        assert_eq!(finding.changed_location.map(|x| (x.file.ends_with("N04-after.capnp"), x.line, x.column)), Some((true, 28, 3)));
+    }
+
+    #[test]
+    fn test_locate_renamed_interface() {
+        let finding = locate_renamed("N06", 0xda55b331806ed8e2, "Sample", "Example", "Sample.initialize");
+        assert_eq!(finding.changed_location.map(|x| (x.line, x.column)), Some((40, 5)));
+    }
+
+    #[test]
+    fn test_locate_renamed_enum() {
+        let finding = locate_renamed("N07", 0xefe94bdb1c4ec715, "Date.EnumTest", "Date.EnumTry", "Date.EnumTest.foo");
+        assert_eq!(finding.base_location.map(|x| (x.line, x.column)), Some((16, 5)));
+        assert_eq!(finding.changed_location.map(|x| (x.line, x.column)), Some((16, 5)));
+    }
+}
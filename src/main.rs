@@ -1,19 +1,57 @@
+mod compare;
+mod compile;
+mod git;
 mod is_broken;
+mod locator;
+mod render;
+mod report;
 mod util;
-use crate::is_broken::*;
+use crate::compare::compare;
+use crate::compare::Request;
+use crate::compare::RequestedFile;
+use crate::compile::run_capnp;
+use crate::compile::CompileError;
+use crate::compile::Side;
+use crate::git::GitTree;
+use crate::locator::Locator;
+use crate::render::render;
+use crate::render::Format;
+use crate::render::REQUIREMENTS_PATH;
+use crate::report::Report;
+use crate::report::Severity;
 
+use std::io::IsTerminal;
+use std::path::Path;
 use std::path::PathBuf;
+use std::process::ExitCode;
 use capnp::serialize;
-use capnpc::codegen::GeneratorContext;
+use clap::CommandFactory;
 use clap::Parser;
 
 #[derive(Parser, Debug)]
 struct Args {
-    /// the path to the base capn'proto schema file
-    base_file_path: String,
-    /// the path to the changed capn'proto schema file
-    changed_file_path: String,
-    /// whether to output to file. The default value is None, and does not output as a file.
+    /// the path to the base capn'proto schema file, or to its CodeGeneratorRequest compiled by `capnp compile -o-`,
+    /// or to the directory of the base schema files.
+    /// With --git-base, the path to the changed schema file in the working copy.
+    #[arg(required_unless_present_any = ["baseline", "pairs"])]
+    base_file_path: Option<String>,
+    /// the path to the changed capn'proto schema file, or to its CodeGeneratorRequest compiled by `capnp compile -o-`,
+    /// or to the directory of the changed schema files.
+    #[arg(required_unless_present_any = ["baseline", "git_base", "pairs"])]
+    changed_file_path: Option<String>,
+    /// a pair of the base and the changed file to compare. It can be given several times.
+    #[arg(long = "pair", num_args = 2, value_names = ["BASE", "CHANGED"], conflicts_with_all = ["base_file_path", "git_base"])]
+    pairs: Vec<String>,
+    /// the path to the base schema file or CodeGeneratorRequest in the plugin mode, which reads the changed CodeGeneratorRequest from stdin
+    #[arg(long, env = "CAPNPC_ANGY_BASELINE")]
+    baseline: Option<String>,
+    /// the git revision to read the base schema file and its imports from, e.g. origin/main
+    #[arg(long, conflicts_with = "changed_file_path")]
+    git_base: Option<String>,
+    /// the Cap'n Proto compiler to compile the schema files with
+    #[arg(long, default_value = "capnp")]
+    capnp_bin: String,
+    /// the path to write the report to. The default value is None, and the report is written to stdout.
     #[arg(short, long, default_value=None)]
     output_file_path: Option<String>,
     /// whether to import the standard path("/usr/local/include" and "/usr/include") or not
@@ -25,6 +63,16 @@ struct Args {
     /// prefixes of the schema file
     #[arg(short, long, default_values_t = Vec::<String>::new(), num_args(0..))]
     src_prefixes: Vec<String>,
+    /// the minimum severity of findings which makes the exit code non-zero [default: warning, or breaking in the plugin mode]
+    #[arg(long, value_enum)]
+    fail_on: Option<Severity>,
+    /// the format of the report
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// the URL of doc/requirements.md which the rule IDs in the markdown and html reports link to,
+    /// e.g. https://github.com/OWNER/REPO/blob/main/doc/requirements.md
+    #[arg(long, default_value = REQUIREMENTS_PATH)]
+    requirements_url: String,
 }
 
 struct ReadWrapper<R>
@@ -49,61 +97,309 @@ where
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>>{
+/// Exit code when no finding reaches the severity given by `--fail-on`.
+const EXIT_SUCCESS: u8 = 0;
+/// Exit code when the schema files cannot be compiled or read.
+const EXIT_ERROR: u8 = 1;
+/// Exit code when some findings reach `--fail-on`, but none of them is breaking.
+const EXIT_WARNING: u8 = 3;
+/// Exit code when a breaking change is found.
+const EXIT_BREAKING: u8 = 4;
+
+fn main() -> ExitCode {
     let args = Args::parse();
-    let base_file = PathBuf::from(args.base_file_path);
+    if let Err(e) = check_args(&args) {
+        e.exit();
+    }
+    match run(&args) {
+        Ok(report) => ExitCode::from(get_exit_code(&report, get_fail_on(&args))),
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+/// A file to compare.
+struct Input {
+    path: String,
+    /// the name to pair the file by when its ID is changed. None is the filename in the request.
+    name: Option<String>,
+}
+
+/// Checks the combinations of the arguments which clap cannot express, as a usage error.
+fn check_args(args: &Args) -> Result<(), clap::Error> {
+    // --baseline (or CAPNPC_ANGY_BASELINE) makes the changed file optional for clap, but it is only used without file paths.
+    if args.base_file_path.is_some() && args.changed_file_path.is_none() && args.git_base.is_none() {
+        return Err(Args::command().error(
+            clap::error::ErrorKind::MissingRequiredArgument,
+            "the changed file path is required unless --git-base is given",
+        ));
+    }
+    Ok(())
+}
+
+/// The files to compare on each side. The changed side is None in the plugin mode, which reads it from stdin.
+struct Inputs {
+    base: Vec<Input>,
+    changed: Option<Vec<Input>>,
+}
+
+fn run(args: &Args) -> Result<Report, Box<dyn std::error::Error>> {
+    let git_tree = args.git_base.as_deref().map(GitTree::export).transpose()?;
+    let inputs = get_inputs(args, git_tree.is_some())?;
+
+    let base_requests = match &git_tree {
+        Some(git_tree) => inputs.base.iter().map(|x| read_git_request(args, git_tree, x)).collect::<Result<Vec<_>, _>>()?,
+        None => read_requests(args, Side::Base, &inputs.base)?,
+    };
+    let changed_requests = match inputs.changed {
+        Some(changed_inputs) => read_requests(args, Side::Changed, &changed_inputs)?,
+        // This runs as a plugin of `capnp compile`, which writes the request to stdin.
+        None => {
+            let message = serialize::read_message(
+                ReadWrapper { inner: std::io::stdin().lock() },
+                capnp::message::ReaderOptions::new(),
+            )
+            .map_err(|source| CompileError::Read { side: Side::Changed, path: "stdin".to_string(), source })?;
+            vec![Request { message, files: vec![] }]
+        }
+    };
+    let report = compare(&base_requests, &changed_requests)?;
+
+    // Colors are only for a terminal. See https://no-color.org for NO_COLOR.
+    let color = args.output_file_path.is_none() && std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let rendered = render(&report, args.format, color, &args.requirements_url);
+    match &args.output_file_path {
+        Some(output_file_path) => write_atomically(&PathBuf::from(output_file_path), &rendered)?,
+        None => print!("{}", rendered),
+    }
+
+    Ok(report)
+}
+
+fn get_inputs(args: &Args, has_git_base: bool) -> Result<Inputs, Box<dyn std::error::Error>> {
+    // Files of a pair are paired by the path of the base file.
+    let pair = |base_file_path: &str, changed_file_path: &str| {
+        (
+            Input { path: base_file_path.to_string(), name: Some(base_file_path.to_string()) },
+            Input { path: changed_file_path.to_string(), name: Some(base_file_path.to_string()) },
+        )
+    };
+    if !args.pairs.is_empty() {
+        let (base, changed) = args.pairs.chunks(2).map(|x| pair(&x[0], &x[1])).unzip();
+        return Ok(Inputs { base, changed: Some(changed) });
+    }
+
+    match (&args.base_file_path, &args.changed_file_path) {
+        // With --git-base, the file in the working copy is compared with itself at the revision.
+        (Some(changed_file_path), None) if has_git_base => {
+            let (base, changed) = pair(changed_file_path, changed_file_path);
+            Ok(Inputs { base: vec![base], changed: Some(vec![changed]) })
+        }
+        (Some(base_dir), Some(changed_dir)) if Path::new(base_dir).is_dir() && Path::new(changed_dir).is_dir() => {
+            Ok(Inputs { base: get_dir_inputs(Path::new(base_dir))?, changed: Some(get_dir_inputs(Path::new(changed_dir))?) })
+        }
+        (Some(base_file_path), Some(changed_file_path)) => {
+            let (base, changed) = pair(base_file_path, changed_file_path);
+            Ok(Inputs { base: vec![base], changed: Some(vec![changed]) })
+        }
+        (None, None) => {
+            let baseline = args.baseline.as_ref().ok_or("the baseline is not given")?;
+            Ok(Inputs { base: vec![Input { path: baseline.clone(), name: None }], changed: None })
+        }
+        _ => Err("the changed file is not given".into()),
+    }
+}
+
+/// Returns the schema files in the directory and its subdirectories, named by the path relative to the directory.
+fn get_dir_inputs(dir: &Path) -> Result<Vec<Input>, Box<dyn std::error::Error>> {
+    let mut paths = vec![];
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current_dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&current_dir).map_err(|e| format!("{}: {}", current_dir.display(), e))? {
+            let entry = entry?;
+            let path = entry.path();
+            // Symlinked directories are not followed, so that a symlink cycle does not make the walk endless.
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else if path.is_file() && path.extension().is_some_and(|x| x == "capnp") {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+
+    let mut inputs = vec![];
+    for path in paths {
+        let name = path.strip_prefix(dir)?.components().map(|x| x.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        inputs.push(Input { path: path.display().to_string(), name: Some(name) });
+    }
+    Ok(inputs)
+}
+
+/// Reads the CodeGeneratorRequests of the files. Schema files are compiled by capnp at once, and request files are read as is.
+fn read_requests(args: &Args, side: Side, inputs: &[Input]) -> Result<Vec<Request>, CompileError> {
+    let mut requests = vec![];
+    let mut schema_inputs = vec![];
+    for input in inputs {
+        let path = &input.path;
+        let open_error = |source| CompileError::Open { side, path: path.to_string(), source };
+        if !is_request_file(Path::new(path)).map_err(open_error)? {
+            schema_inputs.push(input);
+            continue;
+        }
+        let file = std::fs::File::open(path).map_err(open_error)?;
+        let message = serialize::read_message(
+            ReadWrapper { inner: std::io::BufReader::new(file) },
+            capnp::message::ReaderOptions::new(),
+        )
+        .map_err(|source| CompileError::Read { side, path: path.to_string(), source })?;
+        // A request file has no source positions.
+        let files = vec![RequestedFile { name: input.name.clone(), locator: None }];
+        requests.push(Request { message, files });
+    }
+    if schema_inputs.is_empty() {
+        return Ok(requests);
+    }
+
     let stdout = run_capnp(
+        &args.capnp_bin,
         args.no_standard_import, 
         args.import_paths.iter().map(PathBuf::from).collect(), 
         args.src_prefixes.iter().map(PathBuf::from).collect(), 
-        base_file);
+        schema_inputs.iter().map(|x| PathBuf::from(&x.path)).collect(),
+        None,
+        side)?;
+    let paths: Vec<&str> = schema_inputs.iter().map(|x| x.path.as_str()).collect();
     let message = serialize::read_message(
-        ReadWrapper { inner: stdout },
+        ReadWrapper { inner: stdout.as_slice() },
         capnp::message::ReaderOptions::new(),
-    )?;
-    let base_ctx: GeneratorContext = GeneratorContext::new(&message)?;
+    )
+    .map_err(|source| CompileError::Read { side, path: format!("the output of `{}` for {}", args.capnp_bin, paths.join(", ")), source })?;
+    let files = schema_inputs
+        .iter()
+        .map(|x| RequestedFile { name: x.name.clone(), locator: Locator::from_file(Path::new(&x.path)).ok() })
+        .collect();
+    requests.push(Request { message, files });
+    Ok(requests)
+}
 
-    let chnaged_file = PathBuf::from(args.changed_file_path);
+/// Compiles the schema file at the git revision, with the import paths and the source prefixes in the exported tree.
+fn read_git_request(args: &Args, git_tree: &GitTree, input: &Input) -> Result<Request, Box<dyn std::error::Error>> {
+    let path = &input.path;
+    let schema_path = git_tree.get_schema_path(path)?;
     let stdout = run_capnp(
-        args.no_standard_import, 
-        args.import_paths.iter().map(PathBuf::from).collect(), 
-        args.src_prefixes.iter().map(PathBuf::from).collect(), 
-        chnaged_file);
+        &args.capnp_bin,
+        args.no_standard_import,
+        args.import_paths.iter().map(|x| git_tree.get_path(x)).collect(),
+        args.src_prefixes.iter().map(|x| git_tree.get_path(x)).collect(),
+        vec![schema_path.clone()],
+        Some(&git_tree.get_current_dir()),
+        Side::Base)?;
     let message = serialize::read_message(
-        ReadWrapper { inner: stdout },
+        ReadWrapper { inner: stdout.as_slice() },
         capnp::message::ReaderOptions::new(),
-    )?;
-    let changed_ctx: GeneratorContext = GeneratorContext::new(&message)?;
+    )
+    .map_err(|source| CompileError::Read { side: Side::Base, path: git_tree.get_display_name(path), source })?;
+    let locator = std::fs::read_to_string(git_tree.get_current_dir().join(schema_path))
+        .ok()
+        .map(|source| Locator::new(&git_tree.get_display_name(path), &source));
+    Ok(Request { message, files: vec![RequestedFile { name: input.name.clone(), locator }] })
+}
+
+/// Returns whether the file is a binary CodeGeneratorRequest, e.g. the output of `capnp compile -o-`.
+fn is_request_file(path: &Path) -> std::io::Result<bool> {
+    // A message starts with the number of segments minus one as a little-endian u32, whose upper bytes are zero.
+    // A schema file is text, which never contains a NUL byte.
+    let mut header = [0u8; 4];
+    let mut file = std::fs::File::open(path)?;
+    let length = std::io::Read::read(&mut file, &mut header)?;
+    Ok(header[..length].contains(&0))
+}
 
-    // loop based on the nodes in base schema 
-    for requested_file in base_ctx.request.get_requested_files()? {
-        let _ = is_broken(&base_ctx, &changed_ctx, requested_file.get_id());
+/// Writes the content to a temporary file next to `path` and renames it, so that readers never see a partial report.
+fn write_atomically(path: &Path, content: &str) -> std::io::Result<()> {
+    let file_name = path.file_name().map_or("report".into(), |x| x.to_string_lossy());
+    let temporary_path = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+    let result = std::fs::write(&temporary_path, content).and_then(|_| std::fs::rename(&temporary_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temporary_path);
     }
+    result
+}
 
-    Ok(())
+/// Returns `--fail-on`. In the plugin mode, it defaults to breaking, so that `capnp compile` only fails on a breaking change.
+fn get_fail_on(args: &Args) -> Severity {
+    let is_plugin = args.pairs.is_empty() && args.base_file_path.is_none();
+    match args.fail_on {
+        Some(fail_on) => fail_on,
+        None if is_plugin => Severity::Breaking,
+        None => Severity::Warning,
+    }
 }
 
-fn run_capnp(no_standard_import: bool, import_paths: Vec<PathBuf>, src_prefixes: Vec<PathBuf>, target_file: PathBuf) -> std::process::ChildStdout {
-    let mut command = ::std::process::Command::new("capnp");
-    command.env_remove("PWD");
-    command.arg("compile").arg("-o").arg("-");
-    if no_standard_import {
-        command.arg("--no-standard-import");
+fn get_exit_code(report: &Report, fail_on: Severity) -> u8 {
+    match report.max_severity() {
+        Some(Severity::Breaking) if fail_on <= Severity::Breaking => EXIT_BREAKING,
+        Some(severity) if fail_on <= severity => EXIT_WARNING,
+        _ => EXIT_SUCCESS,
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Finding;
+    use crate::report::Rule;
 
-    for import_path in import_paths {
-        command.arg(&format!("--import-path={}", import_path.display()));
+    fn get_report(severities: &[Severity]) -> Report {
+        let mut report = Report::new();
+        for severity in severities {
+            report.push(Finding::new(Rule::C03, *severity, "Person.email", "the type of the field is changed."));
+        }
+        report
     }
 
-    for src_prefix in src_prefixes {
-        command.arg(&format!("--src-prefix={}", src_prefix.display()));
+    fn get_exit_code_with(command: &[&str], severities: &[Severity]) -> u8 {
+        let args = Args::try_parse_from([&["capnpc-angy"], command].concat()).unwrap();
+        get_exit_code(&get_report(severities), get_fail_on(&args))
     }
 
-    command.arg(target_file);
+    #[test]
+    fn test_exit_code() {
+        let command = ["base.capnp", "changed.capnp"];
+        assert_eq!(get_exit_code_with(&command, &[]), EXIT_SUCCESS);
+        assert_eq!(get_exit_code_with(&command, &[Severity::Info]), EXIT_SUCCESS);
+        assert_eq!(get_exit_code_with(&command, &[Severity::Info, Severity::Warning]), EXIT_WARNING);
+        assert_eq!(get_exit_code_with(&command, &[Severity::Warning, Severity::Breaking]), EXIT_BREAKING);
+    }
 
-    command.stdout(::std::process::Stdio::piped());
-    command.stderr(::std::process::Stdio::inherit());
+    #[test]
+    fn test_exit_code_with_fail_on() {
+        let command = ["--fail-on", "breaking", "base.capnp", "changed.capnp"];
+        assert_eq!(get_exit_code_with(&command, &[Severity::Warning]), EXIT_SUCCESS);
+        assert_eq!(get_exit_code_with(&command, &[Severity::Breaking]), EXIT_BREAKING);
+        let command = ["--fail-on", "info", "base.capnp", "changed.capnp"];
+        assert_eq!(get_exit_code_with(&command, &[Severity::Info]), EXIT_WARNING);
+    }
 
-    let mut p = command.spawn().unwrap();
-    p.stdout.take().unwrap()
-}
\ No newline at end of file
+    #[test]
+    fn test_check_args() {
+        let check = |command: &[&str]| check_args(&Args::try_parse_from([&["capnpc-angy"], command].concat()).unwrap());
+        assert!(check(&["base.capnp", "changed.capnp"]).is_ok());
+        assert!(check(&["--git-base", "HEAD", "changed.capnp"]).is_ok());
+        assert!(check(&["--baseline", "baseline.bin"]).is_ok());
+        let e = check(&["--baseline", "baseline.bin", "changed.capnp"]).unwrap_err();
+        assert_eq!(e.exit_code(), 2);
+    }
+
+    #[test]
+    fn test_exit_code_in_plugin_mode() {
+        let command = ["--baseline", "baseline.bin"];
+        assert_eq!(get_exit_code_with(&command, &[Severity::Warning]), EXIT_SUCCESS);
+        assert_eq!(get_exit_code_with(&command, &[Severity::Breaking]), EXIT_BREAKING);
+        let command = ["--baseline", "baseline.bin", "--fail-on", "warning"];
+        assert_eq!(get_exit_code_with(&command, &[Severity::Warning]), EXIT_WARNING);
+    }
+}
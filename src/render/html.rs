@@ -0,0 +1,74 @@
+use super::get_rule_link;
+use super::get_summary_text;
+use super::group_findings;
+use crate::report::Finding;
+use crate::report::Report;
+
+/// Inlined so that the page is a single file.
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }
+th { background: #f4f4f4; }
+code { white-space: pre-wrap; }
+.breaking { color: #b00020; font-weight: bold; }
+.warning { color: #a15c00; }
+.info { color: #555; }
+";
+
+/// Renders the report as a self-contained HTML page, with a table per top-level type.
+pub fn render(report: &Report, requirements_url: &str) -> String {
+    let mut ret = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    ret.push_str("<title>Compatibility report</title>\n");
+    ret.push_str(&format!("<style>\n{}</style>\n", STYLE));
+    ret.push_str("</head>\n<body>\n<h1>Compatibility report</h1>\n");
+    ret.push_str(&format!("<p>{}</p>\n", to_html_text(&get_summary_text(report))));
+    for file in group_findings(report) {
+        ret.push_str(&format!("<h2>{}</h2>\n", to_html_text(file.name)));
+        for type_group in file.types {
+            if !type_group.name.is_empty() {
+                ret.push_str(&format!("<h3>{}</h3>\n", to_html_text(type_group.name)));
+            }
+            ret.push_str("<table>\n<tr><th>Severity</th><th>Rule</th><th>Path</th><th>Base</th><th>Changed</th><th>Message</th></tr>\n");
+            for finding in type_group.findings {
+                ret.push_str(&render_row(finding, requirements_url));
+            }
+            ret.push_str("</table>\n");
+        }
+    }
+    ret.push_str("</body>\n</html>\n");
+    ret
+}
+
+fn render_row(finding: &Finding, requirements_url: &str) -> String {
+    format!(
+        "<tr><td class=\"{severity}\">{severity}</td><td><a href=\"{}\" title=\"{}\">{}</a></td><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+        to_html_text(&get_rule_link(requirements_url, finding.rule)),
+        to_html_text(finding.rule.summary()),
+        finding.rule,
+        to_html_text(&finding.get_member_text()),
+        finding.base.as_deref().map_or(String::new(), to_html_code),
+        finding.changed.as_deref().map_or(String::new(), to_html_code),
+        to_html_text(&finding.message),
+        severity = finding.severity,
+    )
+}
+
+fn to_html_code(value: &str) -> String {
+    format!("<code>{}</code>", to_html_text(value))
+}
+
+fn to_html_text(value: &str) -> String {
+    let mut ret = String::new();
+    for c in value.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&#39;"),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
@@ -0,0 +1,180 @@
+use crate::report::Finding;
+use crate::report::Location;
+use crate::report::Report;
+
+/// The version of the JSON layout. Increment it when a key is renamed, removed or changes its meaning.
+const SCHEMA_VERSION: u32 = 1;
+
+/// A minimal JSON document which keeps the order of object members.
+pub enum Json {
+    Null,
+    Number(u64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    pub fn string(value: impl Into<String>) -> Json {
+        Json::String(value.into())
+    }
+
+    pub fn optional_string(value: Option<impl Into<String>>) -> Json {
+        value.map_or(Json::Null, Json::string)
+    }
+
+    /// Returns the document with 2-space indentation.
+    pub fn to_pretty_string(&self) -> String {
+        let mut ret = String::new();
+        self.write(&mut ret, 0);
+        ret.push('\n');
+        ret
+    }
+
+    fn write(&self, out: &mut String, indent: usize) {
+        match self {
+            Json::Null => out.push_str("null"),
+            Json::Number(x) => out.push_str(&x.to_string()),
+            Json::String(x) => out.push_str(&to_json_string(x)),
+            Json::Array(items) if items.is_empty() => out.push_str("[]"),
+            Json::Array(items) => {
+                out.push_str("[\n");
+                for (index, item) in items.iter().enumerate() {
+                    out.push_str(&"  ".repeat(indent + 1));
+                    item.write(out, indent + 1);
+                    out.push_str(if index + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&"  ".repeat(indent));
+                out.push(']');
+            }
+            Json::Object(members) if members.is_empty() => out.push_str("{}"),
+            Json::Object(members) => {
+                out.push_str("{\n");
+                for (index, (key, value)) in members.iter().enumerate() {
+                    out.push_str(&"  ".repeat(indent + 1));
+                    out.push_str(&to_json_string(key));
+                    out.push_str(": ");
+                    value.write(out, indent + 1);
+                    out.push_str(if index + 1 < members.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&"  ".repeat(indent));
+                out.push('}');
+            }
+        }
+    }
+}
+
+pub fn render(report: &Report) -> String {
+    Json::Object(vec![
+        ("schema_version", Json::Number(SCHEMA_VERSION as u64)),
+        ("tool", Json::string(env!("CARGO_PKG_NAME"))),
+        ("tool_version", Json::string(env!("CARGO_PKG_VERSION"))),
+        ("findings", Json::Array(report.findings.iter().map(render_finding).collect())),
+    ])
+    .to_pretty_string()
+}
+
+fn render_finding(finding: &Finding) -> Json {
+    Json::Object(vec![
+        ("rule", Json::string(finding.rule.to_string())),
+        ("severity", Json::string(finding.severity.to_string())),
+        ("file", Json::string(&finding.file)),
+        ("path", Json::string(&finding.path)),
+        ("node_id", Json::optional_string(finding.node_id.map(|x| format!("0x{:016x}", x)))),
+        ("ordinal", finding.ordinal.map_or(Json::Null, |x| Json::Number(x as u64))),
+        ("base", Json::optional_string(finding.base.as_deref())),
+        ("changed", Json::optional_string(finding.changed.as_deref())),
+        ("message", Json::string(&finding.message)),
+        ("base_location", render_location(finding.base_location.as_ref())),
+        ("changed_location", render_location(finding.changed_location.as_ref())),
+    ])
+}
+
+fn render_location(location: Option<&Location>) -> Json {
+    location.map_or(Json::Null, |x| {
+        Json::Object(vec![
+            ("file", Json::string(&x.file)),
+            ("line", Json::Number(x.line as u64)),
+            ("column", Json::Number(x.column as u64)),
+        ])
+    })
+}
+
+/// Returns the string as a JSON string literal.
+fn to_json_string(value: &str) -> String {
+    let mut ret = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c if (c as u32) < 0x20 => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Rule;
+    use crate::report::Severity;
+
+    #[test]
+    fn test_to_json_string() {
+        assert_eq!(to_json_string("a \"b\" \\c"), r#""a \"b\" \\c""#);
+        assert_eq!(to_json_string("a\nb\tc\r"), r#""a\nb\tc\r""#);
+        assert_eq!(to_json_string("\u{0}\u{1f}é"), "\"\\u0000\\u001fé\"");
+    }
+
+    #[test]
+    fn test_render() {
+        let mut report = Report::new();
+        let mut finding = Finding::new(Rule::C03, Severity::Breaking, "Person.email", "the type of the field is changed.")
+            .with_values("Text", "Data")
+            .with_ordinal(2);
+        finding.file = "phone.capnp".to_string();
+        finding.node_id = Some(0xaaaa);
+        finding.changed_location = Some(Location { file: "phone-after.capnp".to_string(), line: 30, column: 3 });
+        report.push(finding);
+
+        let expected = format!(
+            r#"{{
+  "schema_version": 1,
+  "tool": "capnpc-angy",
+  "tool_version": "{}",
+  "findings": [
+    {{
+      "rule": "C03",
+      "severity": "breaking",
+      "file": "phone.capnp",
+      "path": "Person.email",
+      "node_id": "0x000000000000aaaa",
+      "ordinal": 2,
+      "base": "Text",
+      "changed": "Data",
+      "message": "the type of the field is changed.",
+      "base_location": null,
+      "changed_location": {{
+        "file": "phone-after.capnp",
+        "line": 30,
+        "column": 3
+      }}
+    }}
+  ]
+}}
+"#,
+            env!("CARGO_PKG_VERSION")
+        );
+        assert_eq!(render(&report), expected);
+    }
+
+    #[test]
+    fn test_render_empty() {
+        assert!(render(&Report::new()).contains("\"findings\": []\n"));
+    }
+}
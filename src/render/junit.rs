@@ -0,0 +1,180 @@
+use crate::report::Finding;
+use crate::report::Report;
+use crate::report::Severity;
+
+/// A test case for a top-level node, e.g. `Person` in `phone.capnp`.
+struct TestCase<'a> {
+    file_name: &'a str,
+    name: &'a str,
+    findings: Vec<&'a Finding>,
+}
+
+/// Renders the report as JUnit XML, with a test suite per file and a test case per top-level node.
+/// A test case with breaking findings has one failure which lists them, and the other findings are written to its output.
+pub fn render(report: &Report) -> String {
+    let test_cases = get_test_cases(report);
+    let mut ret = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    ret.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+        env!("CARGO_PKG_NAME"),
+        test_cases.len(),
+        count_failures(test_cases.iter()),
+    ));
+    let mut file_names: Vec<&str> = vec![];
+    for test_case in test_cases.iter() {
+        if !file_names.contains(&test_case.file_name) {
+            file_names.push(test_case.file_name);
+        }
+    }
+    for file_name in file_names {
+        let suite: Vec<&TestCase> = test_cases.iter().filter(|x| x.file_name == file_name).collect();
+        ret.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+            to_xml_text(file_name),
+            suite.len(),
+            count_failures(suite.iter().copied()),
+        ));
+        for test_case in suite {
+            ret.push_str(&render_test_case(test_case));
+        }
+        ret.push_str("  </testsuite>\n");
+    }
+    ret.push_str("</testsuites>\n");
+    ret
+}
+
+/// Returns the compared top-level nodes, and the files or deleted nodes which only appear in the findings.
+fn get_test_cases(report: &Report) -> Vec<TestCase<'_>> {
+    let mut test_cases: Vec<TestCase> = report
+        .compared_nodes
+        .iter()
+        .map(|x| TestCase { file_name: &x.file, name: &x.path, findings: vec![] })
+        .collect();
+    for finding in report.findings.iter() {
+        let (file_name, name) = (finding.file.as_str(), finding.get_type_name());
+        match test_cases.iter_mut().find(|x| x.file_name == file_name && x.name == name) {
+            Some(test_case) => test_case.findings.push(finding),
+            None => test_cases.push(TestCase { file_name, name, findings: vec![finding] }),
+        }
+    }
+    test_cases
+}
+
+fn count_failures<'a>(test_cases: impl Iterator<Item = &'a TestCase<'a>>) -> usize {
+    test_cases.filter(|x| x.findings.iter().any(|x| x.severity == Severity::Breaking)).count()
+}
+
+fn render_test_case(test_case: &TestCase) -> String {
+    // A finding for the file itself has no top-level node.
+    let name = if test_case.name.is_empty() { test_case.file_name } else { test_case.name };
+    let mut ret = format!(
+        "    <testcase classname=\"{}\" name=\"{}\"",
+        to_xml_text(test_case.file_name),
+        to_xml_text(name),
+    );
+    if test_case.findings.is_empty() {
+        ret.push_str("/>\n");
+        return ret;
+    }
+    ret.push_str(">\n");
+    // A test case has at most one failure, which lists all the breaking findings.
+    let (failures, others): (Vec<&Finding>, Vec<&Finding>) =
+        test_case.findings.iter().partition(|x| x.severity == Severity::Breaking);
+    if !failures.is_empty() {
+        let mut rules: Vec<String> = vec![];
+        for finding in failures.iter() {
+            if !rules.contains(&finding.rule.to_string()) {
+                rules.push(finding.rule.to_string());
+            }
+        }
+        let message = match failures[..] {
+            [finding] => finding.message.clone(),
+            _ => format!("{} breaking findings.", failures.len()),
+        };
+        let body: String = failures.iter().map(|x| format!("{}\n", x)).collect();
+        ret.push_str(&format!(
+            "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+            rules.join(","),
+            to_xml_text(&message),
+            to_xml_text(&body),
+        ));
+    }
+    let output: String = others.iter().map(|x| format!("{}\n", x)).collect();
+    if !output.is_empty() {
+        ret.push_str(&format!("      <system-out>{}</system-out>\n", to_xml_text(&output)));
+    }
+    ret.push_str("    </testcase>\n");
+    ret
+}
+
+fn to_xml_text(value: &str) -> String {
+    let mut ret = String::new();
+    for c in value.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&apos;"),
+            // Control characters other than tab and newline are not allowed in XML 1.0.
+            c if (c as u32) < 0x20 && c != '\t' && c != '\n' => ret.push('\u{fffd}'),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::ComparedNode;
+    use crate::report::Rule;
+
+    fn get_finding(rule: Rule, severity: Severity, path: &str, message: &str) -> Finding {
+        let mut finding = Finding::new(rule, severity, path, message);
+        finding.file = "phone.capnp".to_string();
+        finding
+    }
+
+    #[test]
+    fn test_to_xml_text() {
+        assert_eq!(to_xml_text("<a href=\"b\">&'c'</a>"), "&lt;a href=&quot;b&quot;&gt;&amp;&apos;c&apos;&lt;/a&gt;");
+        assert_eq!(to_xml_text("a\tb\nc\u{0}d\u{1b}"), "a\tb\nc\u{fffd}d\u{fffd}");
+    }
+
+    #[test]
+    fn test_one_failure_per_test_case() {
+        let mut report = Report::new();
+        report.compared_nodes.push(ComparedNode { file: "phone.capnp".to_string(), path: "Person".to_string() });
+        report.compared_nodes.push(ComparedNode { file: "phone.capnp".to_string(), path: "Date".to_string() });
+        report.push(get_finding(Rule::C03, Severity::Breaking, "Person.email", "the type of the field is changed."));
+        report.push(get_finding(Rule::C08, Severity::Breaking, "Person.name", "the field is deleted."));
+        report.push(get_finding(Rule::N02, Severity::Info, "Person.age", "the field is added."));
+
+        let expected = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<testsuites name=\"capnpc-angy\" tests=\"2\" failures=\"1\">
+  <testsuite name=\"phone.capnp\" tests=\"2\" failures=\"1\">
+    <testcase classname=\"phone.capnp\" name=\"Person\">
+      <failure type=\"C03,C08\" message=\"2 breaking findings.\">breaking [C03] phone.capnp:Person.email: the type of the field is changed.
+breaking [C08] phone.capnp:Person.name: the field is deleted.
+</failure>
+      <system-out>info [N02] phone.capnp:Person.age: the field is added.
+</system-out>
+    </testcase>
+    <testcase classname=\"phone.capnp\" name=\"Date\"/>
+  </testsuite>
+</testsuites>
+";
+        assert_eq!(render(&report), expected);
+    }
+
+    #[test]
+    fn test_single_failure_message() {
+        let mut report = Report::new();
+        report.push(get_finding(Rule::C22, Severity::Breaking, "", "the file is removed."));
+        let xml = render(&report);
+        assert!(xml.contains("<testcase classname=\"phone.capnp\" name=\"phone.capnp\">"), "{}", xml);
+        assert!(xml.contains("<failure type=\"C22\" message=\"the file is removed.\">"), "{}", xml);
+        assert_eq!(xml.matches("<failure").count(), 1);
+    }
+}
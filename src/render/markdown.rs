@@ -0,0 +1,66 @@
+use super::get_rule_link;
+use super::get_summary_text;
+use super::group_findings;
+use crate::report::Finding;
+use crate::report::Report;
+
+/// Renders the report as Markdown, with a table per top-level type.
+pub fn render(report: &Report, requirements_url: &str) -> String {
+    let mut ret = String::from("# Compatibility report\n\n");
+    ret.push_str(&get_summary_text(report));
+    ret.push('\n');
+    for file in group_findings(report) {
+        ret.push_str(&format!("\n## {}\n", to_markdown_text(file.name)));
+        for type_group in file.types {
+            if !type_group.name.is_empty() {
+                ret.push_str(&format!("\n### {}\n", to_markdown_text(type_group.name)));
+            }
+            ret.push_str("\n| Severity | Rule | Path | Base | Changed | Message |\n");
+            ret.push_str("| ---- | ---- | ---- | ---- | ---- | ---- |\n");
+            for finding in type_group.findings {
+                ret.push_str(&render_row(finding, requirements_url));
+            }
+        }
+    }
+    ret
+}
+
+fn render_row(finding: &Finding, requirements_url: &str) -> String {
+    format!(
+        "| {} | [{}]({} \"{}\") | {} | {} | {} | {} |\n",
+        finding.severity,
+        finding.rule,
+        get_rule_link(requirements_url, finding.rule),
+        finding.rule.summary(),
+        to_markdown_code(&finding.get_member_text()),
+        finding.base.as_deref().map_or(String::new(), to_markdown_code),
+        finding.changed.as_deref().map_or(String::new(), to_markdown_code),
+        to_markdown_text(&finding.message),
+    )
+}
+
+/// Returns the value as a code span in a table cell.
+fn to_markdown_code(value: &str) -> String {
+    let value = value.replace('|', "\\|").replace('\n', " ");
+    // A code span which contains a backtick is delimited by double backticks.
+    if value.contains('`') {
+        format!("`` {} ``", value)
+    } else {
+        format!("`{}`", value)
+    }
+}
+
+fn to_markdown_text(value: &str) -> String {
+    let mut ret = String::new();
+    for c in value.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '#' => {
+                ret.push('\\');
+                ret.push(c);
+            }
+            '\n' => ret.push(' '),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
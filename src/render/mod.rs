@@ -0,0 +1,111 @@
+mod html;
+mod json;
+mod junit;
+mod markdown;
+mod pretty;
+mod sarif;
+
+use clap::ValueEnum;
+
+use crate::report::Finding;
+use crate::report::Report;
+use crate::report::Rule;
+use crate::report::Severity;
+
+/// The rules in doc/requirements.md, relative to the root of the repository. Links are relative to it by default.
+pub const REQUIREMENTS_PATH: &str = "doc/requirements.md";
+
+/// The format of the rendered report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One line per finding
+    Text,
+    /// Findings grouped by type, colored on a terminal, with a summary
+    Pretty,
+    /// Versioned JSON for other tools
+    Json,
+    /// SARIF 2.1.0 for code scanning dashboards
+    Sarif,
+    /// Markdown tables for review threads and release notes
+    Markdown,
+    /// A self-contained HTML page
+    Html,
+    /// JUnit XML with a test case per top-level node
+    Junit,
+}
+
+/// Renders the report. `color` enables ANSI colors in the formats for a terminal.
+/// `requirements_url` is where the rule IDs link to, e.g. `https://example.com/doc/requirements.md`.
+pub fn render(report: &Report, format: Format, color: bool, requirements_url: &str) -> String {
+    match format {
+        Format::Text => render_text(report),
+        Format::Pretty => pretty::render(report, color),
+        Format::Json => json::render(report),
+        Format::Sarif => sarif::render(report),
+        Format::Markdown => markdown::render(report, requirements_url),
+        Format::Html => html::render(report, requirements_url),
+        Format::Junit => junit::render(report),
+    }
+}
+
+fn render_text(report: &Report) -> String {
+    report.findings.iter().map(|x| format!("{}\n", x)).collect()
+}
+
+/// The findings of a top-level type, e.g. `Person` for `Person.union.phones`.
+struct TypeGroup<'a> {
+    name: &'a str,
+    findings: Vec<&'a Finding>,
+}
+
+/// The findings of a schema file.
+struct FileGroup<'a> {
+    name: &'a str,
+    types: Vec<TypeGroup<'a>>,
+}
+
+/// Groups the findings by file and top-level type, in the order they are found.
+fn group_findings(report: &Report) -> Vec<FileGroup<'_>> {
+    let mut files: Vec<FileGroup> = vec![];
+    for finding in report.findings.iter() {
+        let index = match files.iter().position(|x| x.name == finding.file) {
+            Some(index) => index,
+            None => {
+                files.push(FileGroup { name: &finding.file, types: vec![] });
+                files.len() - 1
+            }
+        };
+        let type_name = finding.get_type_name();
+        let types = &mut files[index].types;
+        match types.iter_mut().find(|x| x.name == type_name) {
+            Some(group) => group.findings.push(finding),
+            None => types.push(TypeGroup { name: type_name, findings: vec![finding] }),
+        }
+    }
+    files
+}
+
+/// Returns the link to the row of the rule in doc/requirements.md, e.g. `doc/requirements.md#c03`.
+fn get_rule_link(requirements_url: &str, rule: Rule) -> String {
+    format!("{}#{}", requirements_url, rule.to_string().to_lowercase())
+}
+
+/// Returns the number of findings by severity, e.g. `3 findings: 1 breaking, 1 warning, 1 info.`
+fn get_summary_text(report: &Report) -> String {
+    if report.findings.is_empty() {
+        return "No findings.".to_string();
+    }
+    let noun = if report.findings.len() == 1 { "finding" } else { "findings" };
+    format!("{} {}: {}.", report.findings.len(), noun, get_count_text(report))
+}
+
+/// Returns the number of findings by severity, e.g. `1 breaking, 1 warning`. Severities without findings are omitted.
+fn get_count_text(report: &Report) -> String {
+    let counts: Vec<String> = [Severity::Breaking, Severity::Warning, Severity::Info]
+        .iter()
+        .map(|severity| (severity, report.findings.iter().filter(|x| x.severity == *severity).count()))
+        .filter(|(_, count)| *count > 0)
+        .map(|(severity, count)| format!("{} {}", count, severity))
+        .collect();
+    counts.join(", ")
+}
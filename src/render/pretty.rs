@@ -0,0 +1,106 @@
+use super::get_count_text;
+use super::group_findings;
+use crate::report::Finding;
+use crate::report::Report;
+use crate::report::Severity;
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders the findings grouped under their top-level type, followed by a summary line.
+pub fn render(report: &Report, color: bool) -> String {
+    let paint = |style: &str, text: &str| {
+        if color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    };
+
+    let report = get_unique_findings(report);
+    let mut ret = String::new();
+    for file in group_findings(&report) {
+        for type_group in file.types {
+            let heading = if type_group.name.is_empty() {
+                file.name.to_string()
+            } else {
+                format!("{}:{}", file.name, type_group.name)
+            };
+            ret.push_str(&paint(BOLD, &heading));
+            ret.push('\n');
+            for finding in type_group.findings {
+                ret.push_str(&render_finding(finding, &paint));
+            }
+            ret.push('\n');
+        }
+    }
+
+    let types = if report.compared_nodes.is_empty() {
+        "no types".to_string()
+    } else if report.compared_nodes.len() == 1 {
+        "1 type".to_string()
+    } else {
+        format!("{} types", report.compared_nodes.len())
+    };
+    let summary = if report.findings.is_empty() {
+        format!("No findings across {}", types)
+    } else {
+        format!("{} across {}", get_count_text(&report), types)
+    };
+    ret.push_str(&paint(BOLD, &summary));
+    ret.push('\n');
+    ret
+}
+
+fn render_finding(finding: &Finding, paint: &impl Fn(&str, &str) -> String) -> String {
+    let style = match finding.severity {
+        Severity::Breaking => RED,
+        Severity::Warning => YELLOW,
+        Severity::Info => CYAN,
+    };
+    // The severity is padded before painting, so that the columns line up with and without colors.
+    let mut ret = format!("  {} {} ", paint(style, &format!("{:<8}", finding.severity.to_string())), finding.rule);
+    // A finding for the file itself is already named by the heading.
+    let member_text = finding.get_member_text();
+    if !member_text.is_empty() {
+        ret.push_str(&format!("{}: ", member_text));
+    }
+    ret.push_str(&finding.message);
+    match (&finding.base, &finding.changed) {
+        (Some(base), Some(changed)) => ret.push_str(&format!(" ({} -> {})", base, changed)),
+        (Some(base), None) => ret.push_str(&format!(" ({} -> none)", base)),
+        (None, Some(changed)) => ret.push_str(&format!(" (none -> {})", changed)),
+        (None, None) => {}
+    }
+    ret.push('\n');
+    if let Some(location) = finding.changed_location.as_ref().or(finding.base_location.as_ref()) {
+        ret.push_str(&format!("           {}\n", paint(DIM, &format!("at {}", location))));
+    }
+    ret
+}
+
+/// Returns the report without the findings which are reported more than once, e.g. for a field and its slot.
+fn get_unique_findings(report: &Report) -> Report {
+    let mut ret = Report::new();
+    ret.compared_nodes = report.compared_nodes.clone();
+    for finding in report.findings.iter() {
+        let is_duplicate = ret.findings.iter().any(|x| {
+            x.rule == finding.rule
+                && x.severity == finding.severity
+                && x.file == finding.file
+                && x.path == finding.path
+                && x.ordinal == finding.ordinal
+                && x.base == finding.base
+                && x.changed == finding.changed
+                && x.message == finding.message
+        });
+        if !is_duplicate {
+            ret.push(finding.clone());
+        }
+    }
+    ret
+}
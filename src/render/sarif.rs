@@ -0,0 +1,156 @@
+use super::json::Json;
+use crate::report::Finding;
+use crate::report::Report;
+use crate::report::Rule;
+use crate::report::Severity;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Renders the report as a SARIF 2.1.0 log with a single run.
+pub fn render(report: &Report) -> String {
+    let rules = Json::Array(Rule::ALL.iter().map(|x| render_rule(*x)).collect());
+    let driver = Json::Object(vec![
+        ("name", Json::string(env!("CARGO_PKG_NAME"))),
+        ("version", Json::string(env!("CARGO_PKG_VERSION"))),
+        ("rules", rules),
+    ]);
+    let run = Json::Object(vec![
+        ("tool", Json::Object(vec![("driver", driver)])),
+        ("results", Json::Array(report.findings.iter().map(render_result).collect())),
+    ]);
+    Json::Object(vec![
+        ("$schema", Json::string(SARIF_SCHEMA)),
+        ("version", Json::string(SARIF_VERSION)),
+        ("runs", Json::Array(vec![run])),
+    ])
+    .to_pretty_string()
+}
+
+fn render_rule(rule: Rule) -> Json {
+    Json::Object(vec![
+        ("id", Json::string(rule.to_string())),
+        ("shortDescription", Json::Object(vec![("text", Json::string(rule.summary()))])),
+    ])
+}
+
+fn render_result(finding: &Finding) -> Json {
+    let level = match finding.severity {
+        Severity::Breaking => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+    };
+    let mut message = finding.message.clone();
+    if let (Some(base), Some(changed)) = (&finding.base, &finding.changed) {
+        message = format!("{} ({} -> {})", message, base, changed);
+    }
+
+    // A finding about the file itself has no path.
+    let name = if finding.path.is_empty() { &finding.file } else { &finding.path };
+    let mut location = vec![];
+    // Results are shown next to the changed schema, which is the one under review.
+    if let Some(source) = finding.changed_location.as_ref().or(finding.base_location.as_ref()) {
+        let region = Json::Object(vec![
+            ("startLine", Json::Number(source.line as u64)),
+            ("startColumn", Json::Number(source.column as u64)),
+        ]);
+        location.push((
+            "physicalLocation",
+            Json::Object(vec![
+                ("artifactLocation", Json::Object(vec![("uri", Json::string(&source.file))])),
+                ("region", region),
+            ]),
+        ));
+    } else if !finding.file.is_empty() {
+        location.push((
+            "physicalLocation",
+            Json::Object(vec![("artifactLocation", Json::Object(vec![("uri", Json::string(&finding.file))]))]),
+        ));
+    }
+    location.push(("logicalLocations", Json::Array(vec![Json::Object(vec![("fullyQualifiedName", Json::string(name))])])));
+
+    Json::Object(vec![
+        ("ruleId", Json::string(finding.rule.to_string())),
+        ("ruleIndex", Json::Number(Rule::ALL.iter().position(|x| *x == finding.rule).unwrap_or_default() as u64)),
+        ("level", Json::string(level)),
+        ("message", Json::Object(vec![("text", Json::string(message))])),
+        ("locations", Json::Array(vec![Json::Object(location)])),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Location;
+
+    fn get_finding(rule: Rule, severity: Severity) -> Finding {
+        let mut finding = Finding::new(rule, severity, "Person.email", "the type of the field is changed.");
+        finding.file = "phone.capnp".to_string();
+        finding
+    }
+
+    #[test]
+    fn test_render_result() {
+        let mut finding = get_finding(Rule::C03, Severity::Breaking).with_values("Text", "Data");
+        finding.base_location = Some(Location { file: "phone-before.capnp".to_string(), line: 29, column: 3 });
+        finding.changed_location = Some(Location { file: "phone-after.capnp".to_string(), line: 30, column: 3 });
+        let expected = r#"{
+  "ruleId": "C03",
+  "ruleIndex": 2,
+  "level": "error",
+  "message": {
+    "text": "the type of the field is changed. (Text -> Data)"
+  },
+  "locations": [
+    {
+      "physicalLocation": {
+        "artifactLocation": {
+          "uri": "phone-after.capnp"
+        },
+        "region": {
+          "startLine": 30,
+          "startColumn": 3
+        }
+      },
+      "logicalLocations": [
+        {
+          "fullyQualifiedName": "Person.email"
+        }
+      ]
+    }
+  ]
+}
+"#;
+        assert_eq!(render_result(&finding).to_pretty_string(), expected);
+    }
+
+    #[test]
+    fn test_render_result_without_location() {
+        let mut finding = get_finding(Rule::C22, Severity::Breaking);
+        finding.path = String::new();
+        let result = render_result(&finding).to_pretty_string();
+        assert!(result.contains("\"uri\": \"phone.capnp\""));
+        assert!(result.contains("\"fullyQualifiedName\": \"phone.capnp\""));
+        assert!(!result.contains("region"));
+    }
+
+    #[test]
+    fn test_level() {
+        let levels = [(Severity::Breaking, "error"), (Severity::Warning, "warning"), (Severity::Info, "note")];
+        for (severity, level) in levels {
+            let result = render_result(&get_finding(Rule::C03, severity)).to_pretty_string();
+            assert!(result.contains(&format!("\"level\": \"{}\"", level)), "{}", result);
+        }
+    }
+
+    #[test]
+    fn test_rule_index() {
+        // ruleIndex points at the rule in tool.driver.rules, which lists Rule::ALL in order.
+        let log = render(&Report::new());
+        for (index, rule) in Rule::ALL.iter().enumerate() {
+            let result = render_result(&get_finding(*rule, Severity::Info)).to_pretty_string();
+            assert!(result.contains(&format!("\"ruleIndex\": {},", index)), "{}", result);
+            assert_eq!(log.find(&format!("\"id\": \"{}\"", rule)), log.match_indices("\"id\": ").nth(index).map(|x| x.0));
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use std::fmt;
+
+use clap::ValueEnum;
+
+/// The IDs of the rules defined in doc/requirements.md.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rule {
+    C01,
+    C02,
+    C03,
+    C04,
+    C05,
+    C06,
+    C07,
+    C08,
+    C09,
+    C10,
+    C11,
+    C12,
+    C13,
+    C14,
+    C15,
+    C16,
+    C17,
+    C18,
+    C19,
+    C20,
+    C21,
+    C22,
+    M01,
+    M02,
+    M03,
+    M04,
+    M05,
+    N02,
+    N05,
+    N10,
+    N11,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 31] = [
+        Rule::C01,
+        Rule::C02,
+        Rule::C03,
+        Rule::C04,
+        Rule::C05,
+        Rule::C06,
+        Rule::C07,
+        Rule::C08,
+        Rule::C09,
+        Rule::C10,
+        Rule::C11,
+        Rule::C12,
+        Rule::C13,
+        Rule::C14,
+        Rule::C15,
+        Rule::C16,
+        Rule::C17,
+        Rule::C18,
+        Rule::C19,
+        Rule::C20,
+        Rule::C21,
+        Rule::C22,
+        Rule::M01,
+        Rule::M02,
+        Rule::M03,
+        Rule::M04,
+        Rule::M05,
+        Rule::N02,
+        Rule::N05,
+        Rule::N10,
+        Rule::N11,
+    ];
+
+    /// Returns the summary of the rule in doc/requirements.md.
+    pub fn summary(&self) -> &'static str {
+        match self {
+            Rule::C01 => "The id is changed.",
+            Rule::C02 => "The id is deleted.",
+            Rule::C03 => "The type of a field is changed.",
+            Rule::C04 => "The name of a field is changed.",
+            Rule::C05 => "The a non-union field becomes union.",
+            Rule::C06 => "The a union field becomes non-union.",
+            Rule::C07 => "The default value of a field is changed.",
+            Rule::C08 => "A field is deleted.",
+            Rule::C09 => "The id is deleted.",
+            Rule::C10 => "A arg type of a method is changed.",
+            Rule::C11 => "A arg of a method is deleted.",
+            Rule::C12 => "A arg of a method is added.(?)",
+            Rule::C13 => "The default value of a arg of a method is changed.(?)",
+            Rule::C14 => "The return type of a method is changed.",
+            Rule::C15 => "The name of a method is changed.",
+            Rule::C16 => "A method is added at the middle of the Interface.",
+            Rule::C17 => "The size of the enum is changed.",
+            Rule::C18 => "The name of a value in the enum is changed.",
+            Rule::C19 => "The data section or the pointer section is shrunk.",
+            Rule::C20 => "A method is deleted.",
+            Rule::C21 => "A type parameter is deleted or moved.",
+            Rule::C22 => "A file is deleted.",
+            Rule::M01 => "New union field is added .",
+            Rule::M02 => "New member is added.",
+            Rule::M03 => "The name of a type parameter is changed.",
+            Rule::M04 => "The type of a const is changed.",
+            Rule::M05 => "The value of a const is changed.",
+            Rule::N02 => "New field is added at the end of the field.",
+            Rule::N05 => "New method is added at the end of the Interface.",
+            Rule::N10 => "New file is added.",
+            Rule::N11 => "The preferred list encoding is changed.",
+        }
+    }
+
+    /// Returns the rule for a finding in the param struct of a method.
+    pub fn for_params(self) -> Rule {
+        match self {
+            Rule::C03 | Rule::C04 | Rule::C05 | Rule::C06 => Rule::C10,
+            Rule::C07 => Rule::C13,
+            Rule::C08 | Rule::C19 => Rule::C11,
+            Rule::N02 => Rule::C12,
+            _ => self,
+        }
+    }
+
+    /// Returns the rule for a finding in the result struct of a method.
+    pub fn for_results(self) -> Rule {
+        match self {
+            Rule::C03 | Rule::C04 | Rule::C05 | Rule::C06 | Rule::C07 | Rule::C08 | Rule::C19 => Rule::C14,
+            _ => self,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+pub enum Severity {
+    /// The change is compatible.
+    Info,
+    /// The change is compatible on the wire, but old readers or the source may be affected.
+    Warning,
+    /// The change breaks the compatibility on the wire.
+    Breaking,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Breaking => write!(f, "breaking"),
+        }
+    }
+}
+
+/// A position in a schema file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: String,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column number, in characters.
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// A difference between the base schema and the changed schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub rule: Rule,
+    pub severity: Severity,
+    /// the display name of the file which contains the change, e.g. `phone.capnp`.
+    pub file: String,
+    /// the path of the node or the member in the file, e.g. `Person.union.phones`. It is empty for the file itself.
+    pub path: String,
+    /// the id of the node which owns the changed member.
+    pub node_id: Option<u64>,
+    /// the ordinal of the changed field, method or enumerant.
+    pub ordinal: Option<u16>,
+    pub base: Option<String>,
+    pub changed: Option<String>,
+    pub message: String,
+    /// the position of the changed member in the base schema file.
+    pub base_location: Option<Location>,
+    /// the position of the changed member in the changed schema file.
+    pub changed_location: Option<Location>,
+}
+
+impl Finding {
+    pub fn new(rule: Rule, severity: Severity, path: &str, message: &str) -> Self {
+        Finding {
+            rule,
+            severity,
+            file: String::new(),
+            path: path.to_string(),
+            node_id: None,
+            ordinal: None,
+            base: None,
+            changed: None,
+            message: message.to_string(),
+            base_location: None,
+            changed_location: None,
+        }
+    }
+
+    pub fn with_values(mut self, base: impl Into<String>, changed: impl Into<String>) -> Self {
+        self.base = Some(base.into());
+        self.changed = Some(changed.into());
+        self
+    }
+
+    pub fn with_ordinal(mut self, ordinal: u16) -> Self {
+        self.ordinal = Some(ordinal);
+        self
+    }
+
+    /// Returns the top-level node which contains the change, e.g. `Person` for `Person.union.phones`.
+    pub fn get_type_name(&self) -> &str {
+        self.path.split_once('.').map_or(&self.path, |(type_name, _)| type_name)
+    }
+
+    /// Returns the path relative to the top-level node with the ordinal, e.g. `union.phones @2`.
+    pub fn get_member_text(&self) -> String {
+        let member_path = self.path.split_once('.').map_or(self.path.as_str(), |(_, member_path)| member_path);
+        match self.ordinal {
+            Some(ordinal) => format!("{} @{}", member_path, ordinal),
+            None => member_path.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}] {}", self.severity, self.rule, self.file)?;
+        if !self.path.is_empty() {
+            write!(f, ":{}", self.path)?;
+        }
+        if let Some(ordinal) = self.ordinal {
+            write!(f, " @{}", ordinal)?;
+        }
+        write!(f, ": {}", self.message)?;
+        match (&self.base, &self.changed) {
+            (Some(base), Some(changed)) => write!(f, " ({} -> {})", base, changed),
+            (Some(base), None) => write!(f, " ({} -> none)", base),
+            (None, Some(changed)) => write!(f, " (none -> {})", changed),
+            (None, None) => Ok(()),
+        }?;
+        match (&self.base_location, &self.changed_location) {
+            (Some(base), Some(changed)) => write!(f, " at {}, {}", base, changed),
+            (Some(location), None) | (None, Some(location)) => write!(f, " at {}", location),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// A top-level node which is compared, e.g. `Person` in `phone.capnp`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComparedNode {
+    pub file: String,
+    pub path: String,
+}
+
+/// The findings collected by the is_broken_* functions.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub findings: Vec<Finding>,
+    pub compared_nodes: Vec<ComparedNode>,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Report::default()
+    }
+
+    pub fn push(&mut self, finding: Finding) {
+        self.findings.push(finding);
+    }
+
+    pub fn extend(&mut self, other: Report) {
+        self.findings.extend(other.findings);
+        self.compared_nodes.extend(other.compared_nodes);
+    }
+
+    /// Sets the node id to the findings which do not have it yet.
+    pub fn set_node_id(&mut self, node_id: u64) {
+        for finding in self.findings.iter_mut().filter(|x| x.node_id.is_none()) {
+            finding.node_id = Some(node_id);
+        }
+    }
+
+    /// Sets the file to the findings which do not have it yet.
+    pub fn set_file(&mut self, file: &str) {
+        for finding in self.findings.iter_mut().filter(|x| x.file.is_empty()) {
+            finding.file = file.to_string();
+        }
+    }
+
+    /// Sets the ordinal to the findings which do not have it yet.
+    pub fn set_ordinal(&mut self, ordinal: u16) {
+        for finding in self.findings.iter_mut().filter(|x| x.ordinal.is_none()) {
+            finding.ordinal = Some(ordinal);
+        }
+    }
+
+    /// Returns the most severe severity in the report, or None if there is no finding.
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.findings.iter().map(|x| x.severity).max()
+    }
+}
@@ -1,19 +1,264 @@
+use std::fmt;
+
+use capnp::schema_capnp::brand;
 use capnp::schema_capnp::field;
+use capnp::schema_capnp::node;
+use capnp::schema_capnp::type_;
+use capnp::schema_capnp::value;
 use capnp::any_pointer;
 use capnp::message;
+use capnp::primitive_list;
 use capnpc::codegen::GeneratorContext;
 
+/// Returns the binary representation of the value as it is encoded in a message.
+/// Primitive values are returned as the little-endian bit pattern that is XORed with the field on the wire,
+/// and pointer values are returned as the words of the canonicalized pointer target.
+pub fn get_default_value_as_bytes(value: value::Reader) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let bytes = match value.which()? {
+        value::Which::Void(()) | value::Which::Interface(()) => vec![],
+        value::Which::Bool(x) => vec![x as u8],
+        value::Which::Int8(x) => x.to_le_bytes().to_vec(),
+        value::Which::Int16(x) => x.to_le_bytes().to_vec(),
+        value::Which::Int32(x) => x.to_le_bytes().to_vec(),
+        value::Which::Int64(x) => x.to_le_bytes().to_vec(),
+        value::Which::Uint8(x) => x.to_le_bytes().to_vec(),
+        value::Which::Uint16(x) => x.to_le_bytes().to_vec(),
+        value::Which::Uint32(x) => x.to_le_bytes().to_vec(),
+        value::Which::Uint64(x) => x.to_le_bytes().to_vec(),
+        value::Which::Float32(x) => x.to_bits().to_le_bytes().to_vec(),
+        value::Which::Float64(x) => x.to_bits().to_le_bytes().to_vec(),
+        value::Which::Enum(x) => x.to_le_bytes().to_vec(),
+        value::Which::Text(x) => x?.as_bytes().to_vec(),
+        value::Which::Data(x) => x?.to_vec(),
+        value::Which::List(x) | value::Which::Struct(x) | value::Which::AnyPointer(x) => get_canonical_bytes(x)?,
+    };
+    Ok(bytes)
+}
 
-fn get_default_value_as_bytes(
-    base_ctx: &GeneratorContext,
-    base_field: field::Reader,
-    value: any_pointer::Reader,
-) {
+fn get_canonical_bytes(value: any_pointer::Reader) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
     let allocator =
-        message::HeapAllocator::new().first_segment_words(value.target_size().unwrap().word_count as u32 + 1);
+        message::HeapAllocator::new().first_segment_words(value.target_size()?.word_count as u32 + 1);
     let mut message = message::Builder::new(allocator);
+    message.set_root_canonical(value)?;
     let words = message.get_segments_for_output()[0];
-    for index in 0..(words.len() / 8) {
-        let bytes = &words[(index * 8)..(index + 1) * 8];
+    Ok(words.to_vec())
+}
+
+/// Returns whether two bit patterns are the same, regarding missing bytes as 0x00.
+pub fn is_same_bit_pattern(base: &[u8], changed: &[u8]) -> bool {
+    let len = base.len().max(changed.len());
+    (0..len).all(|index| base.get(index).unwrap_or(&0) == changed.get(index).unwrap_or(&0))
+}
+
+/// The section of a struct in which a slot is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Data,
+    Pointer,
+}
+
+/// The physical position of a slot in the wire layout of a struct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotLayout {
+    pub section: Section,
+    /// offset from the beginning of the section, in bits for the data section and in pointers for the pointer section.
+    pub offset: u32,
+    pub bit_width: u32,
+}
+
+impl fmt::Display for SlotLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.section {
+            Section::Data => write!(f, "data section bit {} (width {})", self.offset, self.bit_width),
+            Section::Pointer => write!(f, "pointer section pointer {} (width {})", self.offset, self.bit_width),
+        }
+    }
+}
+
+/// Returns the wire layout of the slot, or None if the slot takes no space (Void).
+pub fn get_slot_layout(slot: field::slot::Reader) -> Result<Option<SlotLayout>, Box<dyn std::error::Error>> {
+    let (section, bit_width) = match slot.get_type()?.which()? {
+        type_::Which::Void(()) => return Ok(None),
+        type_::Which::Bool(()) => (Section::Data, 1),
+        type_::Which::Int8(()) | type_::Which::Uint8(()) => (Section::Data, 8),
+        type_::Which::Int16(()) | type_::Which::Uint16(()) | type_::Which::Enum(_) => (Section::Data, 16),
+        type_::Which::Int32(()) | type_::Which::Uint32(()) | type_::Which::Float32(()) => (Section::Data, 32),
+        type_::Which::Int64(()) | type_::Which::Uint64(()) | type_::Which::Float64(()) => (Section::Data, 64),
+        type_::Which::Text(())
+        | type_::Which::Data(())
+        | type_::Which::List(_)
+        | type_::Which::Struct(_)
+        | type_::Which::Interface(_)
+        | type_::Which::AnyPointer(_) => (Section::Pointer, 64),
+    };
+    // The offset of a slot is in multiples of its own size.
+    let offset = match section {
+        Section::Data => slot.get_offset() * bit_width,
+        Section::Pointer => slot.get_offset(),
+    };
+    Ok(Some(SlotLayout { section, offset, bit_width }))
+}
+
+/// Returns the name of the node without the file prefix, e.g. `Date.EnumTest`.
+pub fn get_node_name(ctx: &GeneratorContext, node_id: u64) -> Result<String, Box<dyn std::error::Error>> {
+    let Some(node) = ctx.node_map.get(&node_id) else {
+        return Ok(format!("@0x{:x}", node_id));
+    };
+    let display_name = node.get_display_name()?;
+    if let node::File(_) = node.which()? {
+        return Ok(display_name.to_string());
+    }
+    // The name of a file may contain ':', but the name of a node in the file never does.
+    Ok(display_name.rsplit_once(':').map_or(display_name, |(_, name)| name).to_string())
+}
+
+/// Returns the display name of the file which contains the node, e.g. `phone.capnp` for `phone.capnp:Person`.
+pub fn get_file_name(ctx: &GeneratorContext, node_id: u64) -> Result<String, Box<dyn std::error::Error>> {
+    let Some(node) = ctx.node_map.get(&node_id) else {
+        return Ok(String::new());
+    };
+    let display_name = node.get_display_name()?;
+    if let node::File(_) = node.which()? {
+        return Ok(display_name.to_string());
     }
-}
\ No newline at end of file
+    Ok(display_name.rsplit_once(':').map_or("", |(file, _)| file).to_string())
+}
+
+/// Returns the type as it is written in the schema, e.g. `List(PhoneNumber)`.
+pub fn get_type_name(ctx: &GeneratorContext, type_: type_::Reader) -> Result<String, Box<dyn std::error::Error>> {
+    let name = match type_.which()? {
+        type_::Which::Void(()) => "Void".to_string(),
+        type_::Which::Bool(()) => "Bool".to_string(),
+        type_::Which::Int8(()) => "Int8".to_string(),
+        type_::Which::Int16(()) => "Int16".to_string(),
+        type_::Which::Int32(()) => "Int32".to_string(),
+        type_::Which::Int64(()) => "Int64".to_string(),
+        type_::Which::Uint8(()) => "UInt8".to_string(),
+        type_::Which::Uint16(()) => "UInt16".to_string(),
+        type_::Which::Uint32(()) => "UInt32".to_string(),
+        type_::Which::Uint64(()) => "UInt64".to_string(),
+        type_::Which::Float32(()) => "Float32".to_string(),
+        type_::Which::Float64(()) => "Float64".to_string(),
+        type_::Which::Text(()) => "Text".to_string(),
+        type_::Which::Data(()) => "Data".to_string(),
+        type_::Which::List(x) => format!("List({})", get_type_name(ctx, x.get_element_type()?)?),
+        type_::Which::Enum(x) => get_node_name(ctx, x.get_type_id())?,
+        type_::Which::Struct(x) => get_branded_name(ctx, x.get_type_id(), x.get_brand()?)?,
+        type_::Which::Interface(x) => get_branded_name(ctx, x.get_type_id(), x.get_brand()?)?,
+        type_::Which::AnyPointer(x) => match x.which()? {
+            type_::any_pointer::Unconstrained(_) => "AnyPointer".to_string(),
+            type_::any_pointer::Parameter(x) => {
+                let parameter = ctx
+                    .node_map
+                    .get(&x.get_scope_id())
+                    .and_then(|node| node.get_parameters().ok())
+                    .filter(|parameters| (x.get_parameter_index() as u32) < parameters.len())
+                    .map(|parameters| parameters.get(x.get_parameter_index() as u32).get_name());
+                match parameter {
+                    Some(name) => name?.to_string(),
+                    None => format!("parameter {}", x.get_parameter_index()),
+                }
+            }
+            type_::any_pointer::ImplicitMethodParameter(x) => format!("method parameter {}", x.get_parameter_index()),
+        },
+    };
+    Ok(name)
+}
+
+fn get_branded_name(ctx: &GeneratorContext, node_id: u64, brand: brand::Reader) -> Result<String, Box<dyn std::error::Error>> {
+    let mut name = get_node_name(ctx, node_id)?;
+    let mut arguments = vec![];
+    for scope in brand.get_scopes()?.iter() {
+        if let brand::scope::Bind(bindings) = scope.which()? {
+            for binding in bindings?.iter() {
+                match binding.which()? {
+                    brand::binding::Unbound(()) => arguments.push("AnyPointer".to_string()),
+                    brand::binding::Type(x) => arguments.push(get_type_name(ctx, x?)?),
+                }
+            }
+        }
+    }
+    if !arguments.is_empty() {
+        name = format!("{}({})", name, arguments.join(", "));
+    }
+    Ok(name)
+}
+
+/// Returns the value as it is written in the schema, e.g. `[true, false]`.
+/// Pointer values which cannot be written simply are returned as the hex of their canonical form.
+pub fn get_value_text(
+    ctx: &GeneratorContext,
+    value: value::Reader,
+    type_: type_::Reader,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let text = match value.which()? {
+        value::Which::Void(()) => "void".to_string(),
+        value::Which::Bool(x) => x.to_string(),
+        value::Which::Int8(x) => x.to_string(),
+        value::Which::Int16(x) => x.to_string(),
+        value::Which::Int32(x) => x.to_string(),
+        value::Which::Int64(x) => x.to_string(),
+        value::Which::Uint8(x) => x.to_string(),
+        value::Which::Uint16(x) => x.to_string(),
+        value::Which::Uint32(x) => x.to_string(),
+        value::Which::Uint64(x) => x.to_string(),
+        value::Which::Float32(x) => x.to_string(),
+        value::Which::Float64(x) => x.to_string(),
+        value::Which::Text(x) => format!("{:?}", x?),
+        value::Which::Data(x) => format!("0x{}", get_hex(x?)),
+        value::Which::Enum(x) => get_enumerant_name(ctx, type_, x)?,
+        value::Which::Interface(()) => "null".to_string(),
+        value::Which::List(x) => {
+            if x.is_null() {
+                "null".to_string()
+            } else if let type_::Which::List(list_type) = type_.which()? {
+                match list_type.get_element_type()?.which()? {
+                    type_::Which::Bool(()) => get_list_text(x.get_as::<primitive_list::Reader<bool>>()?.iter()),
+                    type_::Which::Int8(()) => get_list_text(x.get_as::<primitive_list::Reader<i8>>()?.iter()),
+                    type_::Which::Int16(()) => get_list_text(x.get_as::<primitive_list::Reader<i16>>()?.iter()),
+                    type_::Which::Int32(()) => get_list_text(x.get_as::<primitive_list::Reader<i32>>()?.iter()),
+                    type_::Which::Int64(()) => get_list_text(x.get_as::<primitive_list::Reader<i64>>()?.iter()),
+                    type_::Which::Uint8(()) => get_list_text(x.get_as::<primitive_list::Reader<u8>>()?.iter()),
+                    type_::Which::Uint16(()) => get_list_text(x.get_as::<primitive_list::Reader<u16>>()?.iter()),
+                    type_::Which::Uint32(()) => get_list_text(x.get_as::<primitive_list::Reader<u32>>()?.iter()),
+                    type_::Which::Uint64(()) => get_list_text(x.get_as::<primitive_list::Reader<u64>>()?.iter()),
+                    type_::Which::Float32(()) => get_list_text(x.get_as::<primitive_list::Reader<f32>>()?.iter()),
+                    type_::Which::Float64(()) => get_list_text(x.get_as::<primitive_list::Reader<f64>>()?.iter()),
+                    _ => format!("0x{}", get_hex(&get_canonical_bytes(x)?)),
+                }
+            } else {
+                format!("0x{}", get_hex(&get_canonical_bytes(x)?))
+            }
+        }
+        value::Which::Struct(x) | value::Which::AnyPointer(x) => {
+            if x.is_null() {
+                "null".to_string()
+            } else {
+                format!("0x{}", get_hex(&get_canonical_bytes(x)?))
+            }
+        }
+    };
+    Ok(text)
+}
+
+fn get_enumerant_name(ctx: &GeneratorContext, type_: type_::Reader, value: u16) -> Result<String, Box<dyn std::error::Error>> {
+    if let type_::Which::Enum(enum_type) = type_.which()? {
+        if let Some(node) = ctx.node_map.get(&enum_type.get_type_id()) {
+            if let node::Enum(enum_node) = node.which()? {
+                let enumerants = enum_node.get_enumerants()?;
+                if (value as u32) < enumerants.len() {
+                    return Ok(enumerants.get(value as u32).get_name()?.to_string());
+                }
+            }
+        }
+    }
+    Ok(value.to_string())
+}
+
+fn get_list_text<T: ToString>(iter: impl Iterator<Item = T>) -> String {
+    format!("[{}]", iter.map(|x| x.to_string()).collect::<Vec<_>>().join(", "))
+}
+
+fn get_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}